@@ -0,0 +1,45 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use quizanalyze::Answers;
+
+// a few thousand respondents with plain and multi-select answers
+fn synthetic_answers() -> Answers
+{
+    let questions = 20;
+    let respondents = 5000;
+
+    let labels: Vec<String> = (0..questions).map(|index| format!("\"question {index}\"")).collect();
+
+    let mut file = labels.join(", ");
+    for respondent in 0..respondents
+    {
+        let reply: Vec<String> = (0..questions).map(|index| match index%3
+        {
+            0 => format!("a{};b{}", respondent%5, respondent%7),
+            _ => format!("\"answer {}\"", (respondent+index)%11)
+        }).collect();
+
+        file.push('\n');
+        file.push_str(&reply.join(", "));
+    }
+
+    Answers::parse(&file).unwrap()
+}
+
+fn collect_questions(c: &mut Criterion)
+{
+    let answers = synthetic_answers();
+
+    c.bench_function("iter_questions", |b|
+    {
+        b.iter(|| answers.iter_questions().map(|replies| replies.len()).sum::<usize>())
+    });
+
+    c.bench_function("question", |b|
+    {
+        b.iter(|| answers.question("question 19").map(|replies| replies.len()))
+    });
+}
+
+criterion_group!(benches, collect_questions);
+criterion_main!(benches);
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::borrow::Cow;
+
+use crate::{Answers, is_blank};
+
+// running totals of a batch of replies, which can be saved and merged with later batches
+#[derive(PartialEq, Debug)]
+pub struct Aggregate
+{
+    labels: Vec<String>,
+    respondents: u32,
+    sums: Vec<i32>,
+    counts: Vec<u32>,
+    distributions: Vec<HashMap<String, u32>>
+}
+
+impl Aggregate
+{
+    // mappings gives the mappings of a column by its label
+    pub fn from_answers<'a, F>(answers: &Answers, mappings: F) -> Self
+        where
+            F: Fn(&str) -> Cow<'a, HashMap<String, i32>>
+    {
+        let labels = answers.labels().clone();
+        let columns = labels.len();
+
+        let mappings: Vec<Cow<HashMap<String, i32>>> = labels.iter().map(|label| mappings(label)).collect();
+
+        let mut sums = vec![0; columns];
+        let mut counts = vec![0; columns];
+        let mut distributions = vec![HashMap::new(); columns];
+
+        for reply in answers.replies()
+        {
+            for (index, choices) in reply.iter().enumerate()
+            {
+                for choice in choices.iter().filter(|choice| !is_blank(choice))
+                {
+                    sums[index] += mappings[index].get(choice).unwrap_or(&0);
+                    counts[index] += 1;
+
+                    *distributions[index].entry(choice.clone()).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let respondents = answers.replies().len() as u32;
+
+        Aggregate{labels, respondents, sums, counts, distributions}
+    }
+
+    pub fn merge(&mut self, other: Aggregate) -> Result<(), String>
+    {
+        if self.labels!=other.labels
+        {
+            return Err(String::from("cant merge batches with different labels"));
+        }
+
+        self.respondents += other.respondents;
+
+        let columns = self.sums.iter_mut().zip(self.counts.iter_mut())
+            .zip(self.distributions.iter_mut());
+
+        let other_columns = other.sums.into_iter().zip(other.counts)
+            .zip(other.distributions);
+
+        for (((sum, count), distribution), ((other_sum, other_count), other_distribution))
+            in columns.zip(other_columns)
+        {
+            *sum += other_sum;
+            *count += other_count;
+
+            for (choice, amount) in other_distribution
+            {
+                *distribution.entry(choice).or_insert(0) += amount;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn ranked(&self) -> Vec<(&str, Option<f64>)>
+    {
+        let scale = f64::from(self.respondents);
+
+        let mut label_sums: Vec<(&str, Option<f64>)> = self.labels.iter()
+            .zip(self.sums.iter().zip(self.counts.iter()))
+            .map(|(label, (sum, count))| (&label[..], (*count!=0).then(|| f64::from(*sum)/scale)))
+            .skip(1)
+            .collect();
+
+        label_sums.sort_by(|other, current|
+        {
+            current.1.partial_cmp(&other.1).unwrap_or(Ordering::Less)
+        });
+
+        label_sums
+    }
+
+    pub fn serialize(&self) -> String
+    {
+        let mut out = format!("respondents\t{}\n", self.respondents);
+
+        for (index, label) in self.labels.iter().enumerate()
+        {
+            out.push_str(&format!("column\t{}\t{}\t{}\n",
+                escape(label), self.sums[index], self.counts[index]));
+
+            let mut values: Vec<(&String, &u32)> = self.distributions[index].iter().collect();
+            values.sort();
+
+            for (choice, amount) in values
+            {
+                out.push_str(&format!("value\t{}\t{amount}\n", escape(choice)));
+            }
+        }
+
+        out
+    }
+
+    pub fn deserialize(text: &str) -> Result<Self, String>
+    {
+        let mut lines = text.lines();
+
+        let respondents = lines.next()
+            .and_then(|line| line.strip_prefix("respondents\t"))
+            .ok_or("state should start with the respondent count")?;
+
+        let mut aggregate = Aggregate{
+            labels: Vec::new(),
+            respondents: parse_number(respondents)?,
+            sums: Vec::new(),
+            counts: Vec::new(),
+            distributions: Vec::new()
+        };
+
+        for line in lines
+        {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields[..]
+            {
+                ["column", label, sum, count] =>
+                {
+                    aggregate.labels.push(unescape(label));
+                    aggregate.sums.push(parse_number(sum)?);
+                    aggregate.counts.push(parse_number(count)?);
+                    aggregate.distributions.push(HashMap::new());
+                },
+                ["value", choice, amount] =>
+                {
+                    let distribution = aggregate.distributions.last_mut()
+                        .ok_or("value before any column in state")?;
+
+                    distribution.insert(unescape(choice), parse_number(amount)?);
+                },
+                _ => return Err(format!("invalid state line: {line}"))
+            }
+        }
+
+        Ok(aggregate)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, String>
+    where
+        T::Err: std::fmt::Display
+{
+    text.parse().map_err(|error| format!("{error}"))
+}
+
+fn escape(text: &str) -> String
+{
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String
+{
+    let mut out = String::new();
+
+    let mut chars = text.chars();
+    while let Some(c) = chars.next()
+    {
+        if c!='\\'
+        {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next()
+        {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => ()
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn merge_matches_single_pass()
+    {
+        let header = "\"time\", \"q1\", \"q2 \tweird\\\\label\"\n";
+        let first = "\"1\", \"yes\", \"no\"\n\"2\", \"no\", yes;no\n";
+        let second = "\"3\", \"yes\", \"\"\n";
+
+        let mappings = HashMap::from([(String::from("yes"), 1), (String::from("no"), 0)]);
+
+        let aggregate = |text: &str|
+        {
+            Aggregate::from_answers(&Answers::parse(text).unwrap(), |_| Cow::Borrowed(&mappings))
+        };
+
+        let mut merged = aggregate(&(header.to_owned()+first));
+
+        let saved = Aggregate::deserialize(&merged.serialize()).unwrap();
+        assert_eq!(saved, merged);
+
+        merged.merge(aggregate(&(header.to_owned()+second))).unwrap();
+
+        let single = aggregate(&(header.to_owned()+first+second));
+        assert_eq!(merged, single);
+        assert_eq!(merged.ranked(), vec![("q1", Some(2.0/3.0)), ("q2 \tweird\\\\label", Some(1.0/3.0))]);
+
+        let other = aggregate("\"time\", \"q3\"\n\"1\", \"yes\"");
+        assert!(merged.merge(other).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{Config, Answers, json};
+use crate::{mode, map_replies, column_mappings, average, median, is_blank, in_summary};
+
+#[derive(PartialEq, Debug)]
+pub struct QuestionStats
+{
+    pub label: String,
+    pub responses: usize,
+    pub most_popular: Option<String>,
+    pub average: Option<f64>,
+    pub median: Option<f64>
+}
+
+impl QuestionStats
+{
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn new(config: &Config, replies: &[&str]) -> Self
+    {
+        let label = replies[0];
+        let no_label_replies = replies.iter().skip(1).copied();
+
+        let responses = no_label_replies.clone().filter(|text| !is_blank(text)).count();
+        let most_popular = mode(no_label_replies.clone(), None).map(String::from);
+
+        let (average, median) = if config.mappings.is_empty()
+        {
+            (None, None)
+        } else
+        {
+            let mapped = map_replies(no_label_replies, &column_mappings(config, label));
+            (Some(average(&mapped)), Some(median(&mapped)))
+        };
+
+        QuestionStats{label: label.to_string(), responses, most_popular, average, median}
+    }
+
+    // in the same order as the schema, missing values are null
+    fn json_fields(&self) -> Vec<(&'static str, String)>
+    {
+        let null = || String::from("null");
+        let number = |value: Option<f64>|
+        {
+            value.filter(|value| value.is_finite()).map_or_else(null, |value| value.to_string())
+        };
+
+        vec![
+            ("label", json::string(&self.label)),
+            ("responses", self.responses.to_string()),
+            ("most_popular", self.most_popular.as_deref().map_or_else(null, json::string)),
+            ("average", number(self.average)),
+            ("median", number(self.median))
+            ]
+    }
+
+    pub fn to_json(&self) -> String
+    {
+        json::object(self.json_fields().into_iter())
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct AnalysisReport
+{
+    pub questions: Vec<QuestionStats>
+}
+
+impl AnalysisReport
+{
+    // the selected question, or every question when none is selected
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn new(config: &Config, answers: &Answers) -> Result<Self, String>
+    {
+        let questions = if config.selects_question()
+        {
+            vec![QuestionStats::new(config, &crate::question(config, answers)?)]
+        } else
+        {
+            answers.iter_questions()
+                .filter(|replies| in_summary(config, replies[0]))
+                .map(|replies| QuestionStats::new(config, &replies))
+                .collect()
+        };
+
+        Ok(AnalysisReport{questions})
+    }
+
+    pub fn to_json(&self) -> String
+    {
+        let questions = json::array(self.questions.iter().map(QuestionStats::to_json));
+
+        json::object([("questions", questions)].into_iter())
+    }
+
+    // json schema of the report, every field is always present and missing values are null
+    pub fn json_schema() -> String
+    {
+        let typed = |kind: &str| json::object([("type", json::string(kind))].into_iter());
+        let nullable = |kind: &str|
+        {
+            json::object([("type", json::array([kind, "null"].into_iter().map(json::string)))].into_iter())
+        };
+
+        let strict_object = |properties: Vec<(&str, String)>|
+        {
+            let required = json::array(properties.iter().map(|(key, _)| json::string(key)));
+
+            vec![
+                ("type", json::string("object")),
+                ("properties", json::object(properties.into_iter())),
+                ("required", required),
+                ("additionalProperties", String::from("false"))
+            ]
+        };
+
+        let responses = json::object([
+            ("type", json::string("integer")),
+            ("minimum", String::from("0"))
+            ].into_iter());
+
+        let question = json::object(strict_object(vec![
+            ("label", typed("string")),
+            ("responses", responses),
+            ("most_popular", nullable("string")),
+            ("average", nullable("number")),
+            ("median", nullable("number"))
+            ]).into_iter());
+
+        let questions = json::object([
+            ("type", json::string("array")),
+            ("items", question)
+            ].into_iter());
+
+        let header = [
+            ("$schema", json::string("https://json-schema.org/draft/2020-12/schema")),
+            ("title", json::string("AnalysisReport"))
+            ];
+
+        json::object(header.into_iter().chain(strict_object(vec![("questions", questions)])))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn report_schema()
+    {
+        let schema = AnalysisReport::json_schema();
+
+        assert!(schema.starts_with(
+            "{\"$schema\":\"https://json-schema.org/draft/2020-12/schema\",\"title\":\"AnalysisReport\","));
+        assert!(schema.ends_with(",\"required\":[\"questions\"],\"additionalProperties\":false}"));
+        assert!(schema.contains("\"properties\":{\"questions\":{\"type\":\"array\",\"items\":{"));
+
+        for field in ["label", "responses", "most_popular", "average", "median"]
+        {
+            assert!(schema.contains(&format!("\"{field}\":{{\"type\":")), "missing {field}");
+        }
+
+        assert!(schema.contains("\"average\":{\"type\":[\"number\",\"null\"]}"));
+        assert_eq!(schema.matches('{').count(), schema.matches('}').count());
+    }
+
+    #[test]
+    #[allow(clippy::suspicious_else_formatting)]
+    fn report_json_matches_schema()
+    {
+        let schema = AnalysisReport::json_schema();
+
+        let filled = QuestionStats{label: String::from("say \"hi\""), responses: 3,
+            most_popular: Some(String::from("yes")), average: Some(2.5), median: Some(2.0)};
+        let empty = QuestionStats{label: String::from("q2"), responses: 0,
+            most_popular: None, average: None, median: Some(f64::NAN)};
+
+        let kind = |value: &str|
+        {
+            if value.starts_with('"')
+            {
+                "string"
+            } else if value=="null"
+            {
+                "null"
+            } else if value.parse::<u64>().is_ok()
+            {
+                "integer"
+            } else
+            {
+                assert!(value.parse::<f64>().is_ok(), "{value} is not a number");
+                "number"
+            }
+        };
+
+        for stats in [&filled, &empty]
+        {
+            let fields = stats.json_fields();
+
+            let keys: Vec<String> = fields.iter().map(|(key, _)| json::string(key)).collect();
+            assert!(schema.contains(&format!(",\"required\":[{}],", keys.join(","))));
+
+            for (key, value) in fields
+            {
+                let start = schema.find(&format!("\"{key}\":{{\"type\":")).expect("key is in the schema");
+                let types = &schema[start..start+schema[start..].find('}').unwrap()];
+
+                let kind = kind(&value);
+                let allowed = types.contains(&format!("\"{kind}\""))
+                    || (kind=="integer" && types.contains("\"number\""));
+
+                assert!(allowed, "{key} is a {kind} but the schema says {types}");
+            }
+        }
+
+        let report = AnalysisReport{questions: vec![filled, empty]};
+        assert_eq!(report.to_json(), concat!(
+            "{\"questions\":[",
+            "{\"label\":\"say \\\"hi\\\"\",\"responses\":3,\"most_popular\":\"yes\",\"average\":2.5,\"median\":2},",
+            "{\"label\":\"q2\",\"responses\":0,\"most_popular\":null,\"average\":null,\"median\":null}",
+            "]}"));
+        assert!(schema.contains("\"required\":[\"questions\"]"));
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeSet;
+
+use crate::{Answers, is_blank};
+use crate::csv::csv_reader::csv_field;
+
+// how many respondents picked every pair of answers to two questions
+#[derive(PartialEq, Debug)]
+pub struct CrossTab
+{
+    label: String,
+    rows: Vec<String>,
+    columns: Vec<String>,
+    counts: Vec<Vec<u32>>
+}
+
+impl CrossTab
+{
+    pub fn new(answers: &Answers, row_index: usize, column_index: usize) -> Self
+    {
+        let values = |index: usize| -> Vec<String>
+        {
+            let values: BTreeSet<&String> = answers.cells(index).flatten()
+                .filter(|choice| !is_blank(choice))
+                .collect();
+
+            values.into_iter().cloned().collect()
+        };
+
+        let rows = values(row_index);
+        let columns = values(column_index);
+
+        let mut counts = vec![vec![0; columns.len()]; rows.len()];
+        for (row_cell, column_cell) in answers.cells(row_index).zip(answers.cells(column_index))
+        {
+            for row_choice in row_cell.iter().filter(|choice| !is_blank(choice))
+            {
+                for column_choice in column_cell.iter().filter(|choice| !is_blank(choice))
+                {
+                    let row = rows.binary_search(row_choice).expect("rows has every value");
+                    let column = columns.binary_search(column_choice)
+                        .expect("columns has every value");
+
+                    counts[row][column] += 1;
+                }
+            }
+        }
+
+        let label = answers.labels()[row_index].clone();
+
+        CrossTab{label, rows, columns, counts}
+    }
+
+    // replaces the label in the corner of the table
+    pub fn with_label(mut self, label: &str) -> Self
+    {
+        self.label = label.to_owned();
+
+        self
+    }
+
+    pub fn lines(&self) -> Vec<String>
+    {
+        self.table().into_iter().map(|fields| fields.join("\t")).collect()
+    }
+
+    pub fn to_csv(&self) -> String
+    {
+        self.table().into_iter().map(|fields|
+        {
+            let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            fields.join(",")+"\n"
+        }).collect()
+    }
+
+    // the header row followed by every row label with its counts
+    fn table(&self) -> Vec<Vec<String>>
+    {
+        let header = std::iter::once(self.label.clone()).chain(self.columns.iter().cloned());
+
+        std::iter::once(header.collect())
+            .chain(self.rows.iter().zip(self.counts.iter()).map(|(row, counts)|
+            {
+                std::iter::once(row.clone())
+                    .chain(counts.iter().map(|count| count.to_string()))
+                    .collect()
+            }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn crosstab_csv()
+    {
+        let answers = Answers::parse(
+            "\"time\", \"class, section\", \"pets\"
+            \"1\", \"a\", cat;dog
+            \"2\", \"b\", \"dog\"
+            \"3\", \"a\", \"cat\"
+            \"4\", \"\", \"cat\"
+            \"5\", \"b\", \"\"").unwrap();
+
+        let crosstab = CrossTab::new(&answers, 1, 2);
+        assert_eq!(crosstab.lines(), vec!["class, section\tcat\tdog", "a\t2\t1", "b\t0\t1"]);
+
+        let csv = crosstab.to_csv();
+        assert_eq!(csv, "\"class, section\",cat,dog\na,2,1\nb,0,1\n");
+
+        let table = Answers::parse(&csv).unwrap();
+        assert_eq!(table.labels().len(), 3);
+        assert_eq!(table.replies().len(), 2);
+        assert_eq!(table.question("cat"), Some(vec!["cat", "2", "0"]));
+        assert_eq!(table.question("class"), Some(vec!["class, section", "a", "b"]));
+
+    }
+}
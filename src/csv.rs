@@ -1,8 +1,55 @@
 pub mod csv_reader
 {
+    use std::collections::{BTreeMap, HashMap, HashSet};
+    use std::io::{BufRead, Read};
+    use std::time::{Duration, Instant};
+    use std::iter;
+    use std::cmp::Ordering;
+
+    use crate::is_blank;
+
     type Answer = Vec<String>;
     type Reply = Vec<Answer>;
 
+    #[derive(Debug, Clone)]
+    pub struct ParseOptions
+    {
+        pub delimiter: char,
+        pub separator: char,
+        pub quote: char,
+        pub has_header: bool,
+        pub allow_blank_lines: bool,
+        pub require_rectangular: bool,
+        pub widths: Option<Vec<usize>>,
+        pub timeout: Option<Duration>
+    }
+
+    impl Default for ParseOptions
+    {
+        fn default() -> Self
+        {
+            ParseOptions{delimiter: ',', separator: ';', quote: '"', has_header: true,
+                allow_blank_lines: true, require_rectangular: false, widths: None, timeout: None}
+        }
+    }
+
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub enum ColumnType
+    {
+        Numeric,
+        Categorical,
+        Text
+    }
+
+    // what parsing did with the lines of the file, the header counts as a read line
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub struct ParseStats
+    {
+        pub lines: usize,
+        pub skipped: usize,
+        pub rows: usize
+    }
+
     #[derive(PartialEq, Debug)]
     pub struct Answers
     {
@@ -12,19 +59,92 @@ pub mod csv_reader
 
     impl Answers
     {
-        pub fn parse(file: &str) -> Result<Self, &'static str>
+        pub fn parse(file: &str) -> Result<Self, String>
+        {
+            Self::parse_with(file, &ParseOptions::default())
+        }
+
+        pub fn parse_with(file: &str, options: &ParseOptions) -> Result<Self, String>
+        {
+            Self::parse_with_stats(file, options).map(|(answers, _)| answers)
+        }
+
+        // reads everything first, multiline answers need the whole text to be split correctly
+        pub fn parse_from<R: Read>(mut reader: R, options: &ParseOptions) -> Result<Self, String>
+        {
+            let start = Instant::now();
+
+            let mut file = String::new();
+            reader.read_to_string(&mut file).map_err(|error| format!("{error}"))?;
+
+            Self::parse_since(&file, options, start).map(|(answers, _)| answers)
+        }
+
+        pub fn parse_with_stats(
+            file: &str,
+            options: &ParseOptions
+        ) -> Result<(Self, ParseStats), String>
+        {
+            Self::parse_since(file, options, Instant::now())
+        }
+
+        // the timeout counts from start, so the time spent reading the file counts too
+        pub fn parse_since(
+            file: &str,
+            options: &ParseOptions,
+            start: Instant
+        ) -> Result<(Self, ParseStats), String>
         {
-            let answers = parser::parse(file)?;
+            let (answers, stats) = parser::parse(file, options, start)?;
 
-            for batch in &answers.replies
+            // strict mode reports every ragged row instead of stopping at the first
+            let mut ragged = Vec::new();
+            for (index, batch) in answers.replies.iter().enumerate()
             {
                 if batch.len()!=answers.labels.len()
                 {
-                    return Err("replies are not the same size as labels");
+                    if !options.require_rectangular
+                    {
+                        return Err(String::from("replies are not the same size as labels"));
+                    }
+
+                    ragged.push(format!("row {} has {} fields", index+1, batch.len()));
                 }
             }
 
-            Ok(answers)
+            if !ragged.is_empty()
+            {
+                return Err(format!("{}, expected {}", ragged.join(", "), answers.labels.len()));
+            }
+
+            Ok((answers, stats))
+        }
+
+        // default options csv, multiple choices are written unquoted with the separator between
+        #[allow(clippy::suspicious_else_formatting)]
+        pub fn to_csv(&self) -> String
+        {
+            let separator = ParseOptions::default().separator.to_string();
+
+            let header = self.labels.iter().map(|label| csv_field(label));
+            let rows = self.replies.iter().map(|reply|
+            {
+                reply.iter().map(|choices|
+                {
+                    if choices.is_empty()
+                    {
+                        csv_field("")
+                    } else
+                    {
+                        choices.iter().map(|choice| csv_field(choice)).collect::<Vec<String>>()
+                            .join(&separator)
+                    }
+                }).collect::<Vec<String>>()
+            });
+
+            iter::once(header.collect()).chain(rows)
+                .map(|fields: Vec<String>| fields.join(",")+"\n")
+                .collect()
         }
 
         pub fn labels(&self) -> &Vec<String>
@@ -37,34 +157,284 @@ pub mod csv_reader
             &self.replies
         }
 
+        pub fn has_label(&self, name: &str) -> bool
+        {
+            self.label(|label| {label==name}).is_some()
+        }
+
         pub fn reply(&self, index: usize) -> Option<&Vec<Answer>>
         {
             self.replies.get(index)
         }
 
+        pub fn retain_range(&mut self, start: usize, end: usize)
+        {
+            let end = end.min(self.replies.len());
+            let start = start.min(end);
+
+            self.replies.truncate(end);
+            self.replies.drain(..start);
+        }
+
+        // keeps the replies at the given indices in their original order
+        pub fn retain_indices(&mut self, indices: &[usize])
+        {
+            let keep: HashSet<usize> = indices.iter().copied().collect();
+
+            let mut index = 0;
+            self.replies.retain(|_|
+            {
+                index += 1;
+                keep.contains(&(index-1))
+            });
+        }
+
+        pub fn dedupe_by(&mut self, index: usize)
+        {
+            let mut seen = HashSet::new();
+
+            let mut kept: Vec<Reply> = Vec::new();
+            for reply in self.replies.drain(..).rev()
+            {
+                let blank = reply[index].iter().all(|choice| is_blank(choice));
+                if blank || seen.insert(reply[index].clone())
+                {
+                    kept.push(reply);
+                }
+            }
+
+            kept.reverse();
+            self.replies = kept;
+        }
+
+        pub fn map_column<F>(&mut self, label: &str, f: F) -> Result<(), String>
+            where
+                F: Fn(&str) -> String
+        {
+            let index = self.index_of_exact(label).ok_or(format!("cant find {label}"))?;
+
+            let choices = self.replies.iter_mut().flat_map(|reply| reply[index].iter_mut());
+            for choice in choices
+            {
+                *choice = f(choice);
+            }
+
+            Ok(())
+        }
+
+        pub fn sort_respondents_by(&mut self, label: &str) -> Result<(), String>
+        {
+            self.sort_respondents_with(label, |text| text.trim().parse().ok())
+        }
+
+        // numbers in numeric order before everything else in text order, stable so
+        // respondents with the same value keep their file order
+        pub fn sort_respondents_with<F>(&mut self, label: &str, number: F) -> Result<(), String>
+            where
+                F: Fn(&str) -> Option<f64>
+        {
+            let index = self.index_of_exact(label).ok_or(format!("cant find {label}"))?;
+
+            self.replies.sort_by(|a, b|
+            {
+                let key = |reply: &Reply|
+                {
+                    let text = reply[index].first().cloned().unwrap_or_default();
+                    (number(&text), text)
+                };
+
+                match (key(a), key(b))
+                {
+                    ((Some(a), _), (Some(b), _)) => a.total_cmp(&b),
+                    ((Some(_), _), (None, _)) => Ordering::Less,
+                    ((None, _), (Some(_), _)) => Ordering::Greater,
+                    ((None, a), (None, b)) => a.cmp(&b)
+                }
+            });
+
+            Ok(())
+        }
+
+        pub fn map_all<F>(&mut self, f: F)
+            where
+                F: Fn(&str) -> String
+        {
+            let texts = self.labels.iter_mut()
+                .chain(self.replies.iter_mut().flatten().flatten());
+
+            for text in texts
+            {
+                *text = f(text);
+            }
+        }
+
+        pub fn collapse_whitespace(&mut self)
+        {
+            let choices = self.replies.iter_mut().flatten().flatten();
+            for choice in choices
+            {
+                *choice = choice.split_whitespace().collect::<Vec<&str>>().join(" ");
+            }
+        }
+
+        // keeps the first of every repeated choice within a cell
+        pub fn dedupe_choices(&mut self)
+        {
+            for choices in self.replies.iter_mut().flatten()
+            {
+                let mut seen = HashSet::new();
+                choices.retain(|choice| seen.insert(choice.clone()));
+            }
+        }
+
         pub fn question(&self, name: &str) -> Option<Vec<&str>>
         {
-            let index = self.label(|label| {label.contains(name)})?;
+            let index = self.index_of(name)?;
             Some(self.collect(index))
         }
 
         pub fn question_exact(&self, name: &str) -> Option<Vec<&str>>
         {
-            let index = self.label(|label| {label==name})?;
+            let index = self.index_of_exact(name)?;
             Some(self.collect(index))
         }
 
-        fn collect(&self, index: usize) -> Vec<&str>
+        pub fn question_by_index(&self, index: isize) -> Option<Vec<&str>>
+        {
+            let index = self.resolve_index(index)?;
+            Some(self.collect(index))
+        }
+
+        pub fn cells(&self, index: usize) -> impl Iterator<Item=&[String]> + '_
+        {
+            self.replies.iter().map(move |reply| &reply[index][..])
+        }
+
+        pub fn index_of(&self, name: &str) -> Option<usize>
+        {
+            self.label(|label| {label.contains(name)})
+        }
+
+        pub fn index_of_exact(&self, name: &str) -> Option<usize>
+        {
+            self.label(|label| {label==name})
+        }
+
+        #[allow(clippy::suspicious_else_formatting)]
+        pub fn resolve_index(&self, index: isize) -> Option<usize>
         {
-            let mut out = vec![&self.labels[index][..]];
+            let index = if index<0
+            {
+                self.labels.len().checked_sub(index.unsigned_abs())?
+            } else
+            {
+                index as usize
+            };
+
+            if index<self.labels.len()
+            {
+                Some(index)
+            } else
+            {
+                None
+            }
+        }
+
+        pub fn group_by(&self, group: usize, index: usize) -> Vec<(&str, Vec<&str>)>
+        {
+            let mut groups: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
             for reply in &self.replies
             {
-                for choice in &reply[index]
+                let key = match reply[group].iter().find(|choice| !is_blank(choice))
                 {
-                    out.push(&choice[..]);
-                }
+                    Some(key) => key,
+                    None => continue
+                };
+
+                groups.entry(key).or_default()
+                    .extend(reply[index].iter().map(|choice| &choice[..]));
             }
 
+            groups.into_iter().collect()
+        }
+
+        pub fn iter_questions(&self) -> impl Iterator<Item=Vec<&str>> + '_
+        {
+            (0..self.labels.len()).map(|index| self.collect(index))
+        }
+
+        pub fn distinct_values(&self, label: &str) -> Option<Vec<&str>>
+        {
+            let index = self.label(|current| {current==label})?;
+
+            let mut values: Vec<&str> = self.collect(index).into_iter()
+                .skip(1)
+                .filter(|value| !is_blank(value))
+                .collect();
+
+            values.sort_unstable();
+            values.dedup();
+
+            Some(values)
+        }
+
+        // distinct non-empty answers that dont parse as a number
+        pub fn validate_numeric(&self, label: &str) -> Option<Vec<&str>>
+        {
+            let values = self.distinct_values(label)?;
+
+            Some(values.into_iter().filter(|value| value.trim().parse::<f64>().is_err()).collect())
+        }
+
+        // numeric if at least 9 in 10 answers parse as numbers, categorical if the answers
+        // repeat enough that there are at most half as many distinct ones as respondents
+        #[allow(clippy::suspicious_else_formatting)]
+        pub fn column_type(&self, label: &str) -> Option<ColumnType>
+        {
+            let index = self.label(|current| {current==label})?;
+
+            let values: Vec<&str> = self.cells(index).flatten()
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .collect();
+
+            let numeric = values.iter().filter(|value| value.parse::<f64>().is_ok()).count();
+            let distinct: HashSet<&str> = values.iter().copied().collect();
+
+            let column_type = if !values.is_empty() && numeric*10>=values.len()*9
+            {
+                ColumnType::Numeric
+            } else if distinct.len()*2<=self.replies.len()
+            {
+                ColumnType::Categorical
+            } else
+            {
+                ColumnType::Text
+            };
+
+            Some(column_type)
+        }
+
+        // length in characters of every non-empty answer
+        pub fn answer_lengths(&self, label: &str) -> Option<Vec<usize>>
+        {
+            let index = self.label(|current| {current==label})?;
+
+            let lengths = self.cells(index).flatten()
+                .filter(|value| !is_blank(value))
+                .map(|value| value.chars().count())
+                .collect();
+
+            Some(lengths)
+        }
+
+        fn collect(&self, index: usize) -> Vec<&str>
+        {
+            // most cells have a single choice
+            let mut out = Vec::with_capacity(self.replies.len()+1);
+            out.push(&self.labels[index][..]);
+            out.extend(self.cells(index).flatten().map(|choice| &choice[..]));
+
             out
         }
 
@@ -84,17 +454,152 @@ pub mod csv_reader
         }
     }
 
+    // quoted when it would otherwise be split up or lose its surrounding whitespace
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn csv_field(text: &str) -> String
+    {
+        if text.contains([',', '"', ';', '\n', '\r']) || text.trim()!=text || text.is_empty()
+        {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else
+        {
+            text.to_string()
+        }
+    }
+
+    // choices without a trailing :<weight> count once, weights that dont fit are an error
+    pub fn split_weight(choice: &str) -> Result<(&str, u32), String>
+    {
+        let (text, weight) = match choice.rsplit_once(':')
+        {
+            Some((text, weight)) => (text.trim_end(), weight.trim()),
+            None => return Ok((choice, 1))
+        };
+
+        if weight.is_empty() || !weight.chars().all(|c| c.is_ascii_digit())
+        {
+            return Ok((choice, 1));
+        }
+
+        let weight = weight.parse().map_err(|_| format!("weight of {text} is too large: {weight}"))?;
+
+        Ok((text, weight))
+    }
+
+    pub fn stream_frequencies<R, F>(
+        reader: R,
+        options: &ParseOptions,
+        matches: F) -> Result<(String, HashMap<String, u32>), String>
+        where
+            R: BufRead,
+            F: FnMut(&str) -> bool
+    {
+        let mut occurrences: HashMap<String, u32> = HashMap::new();
+        let (_, label) = stream_choices(reader, options, matches, |choice|
+        {
+            *occurrences.entry(choice).or_insert(0) += 1;
+        })?.ok_or("cant find the question")?;
+
+        Ok((label, occurrences))
+    }
+
+    // hands every non empty choice of the first matching question to the callback and returns
+    // its index and label, errors are all parse errors and no question matching is none
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn stream_choices<R, F, C>(
+        reader: R,
+        options: &ParseOptions,
+        mut matches: F,
+        mut choice: C) -> Result<Option<(usize, String)>, String>
+        where
+            R: BufRead,
+            F: FnMut(&str) -> bool,
+            C: FnMut(String)
+    {
+        let start = Instant::now();
+        let mut records = parser::records(reader, options).peekable();
+
+        let labels: Vec<String> = if options.has_header
+        {
+            records.next().ok_or("first line missing")??.into_iter().flatten().collect()
+        } else
+        {
+            let first = records.peek().ok_or("first line missing")?.as_ref()?;
+            parser::numbered_labels(first.len())
+        };
+
+        let fields = labels.len();
+
+        let mut found = None;
+        for (index, label) in labels.into_iter().enumerate()
+        {
+            if matches(&label)
+            {
+                found = Some((index, label));
+                break;
+            }
+        }
+
+        let (index, label) = match found
+        {
+            Some(found) => found,
+            None => return Ok(None)
+        };
+
+        // same checks as the in memory parser, strict mode reports every ragged row
+        let mut ragged = Vec::new();
+        for (row, record) in records.enumerate()
+        {
+            if row%parser::TIMEOUT_CHECK==0
+            {
+                parser::check_timeout(options, start, row+1)?;
+            }
+
+            let mut record = record?;
+            if record.len()!=fields
+            {
+                if !options.require_rectangular
+                {
+                    return Err(String::from("replies are not the same size as labels"));
+                }
+
+                ragged.push(format!("row {} has {} fields", row+1, record.len()));
+                continue;
+            }
+
+            record.swap_remove(index).into_iter().filter(|text| !is_blank(text)).for_each(&mut choice);
+        }
+
+        if !ragged.is_empty()
+        {
+            return Err(format!("{}, expected {fields}", ragged.join(", ")));
+        }
+
+        Ok(Some((index, label)))
+    }
+
     mod parser
     {
         use std::mem;
+        use std::io::BufRead;
+        use std::time::Instant;
 
         use super::Answers;
+        use super::ParseOptions;
+        use super::ParseStats;
         use super::Reply;
 
+        pub const TIMEOUT_CHECK: usize = 1000;
+
         struct State
         {
+            delimiter: char,
+            separator: char,
+            quote: char,
             options: Vec<String>,
             option: String,
+            unquoted: String,
+            quoted: bool,
             special: bool,
             text: bool,
             next: bool,
@@ -103,10 +608,12 @@ pub mod csv_reader
 
         impl State
         {
-            fn new() -> Self
+            fn new(parse_options: &ParseOptions) -> Self
             {
-                State{options: Vec::new(), option: String::new(),
-                    special: false, text: false, next: false, over: false}
+                State{delimiter: parse_options.delimiter, separator: parse_options.separator,
+                    quote: parse_options.quote,
+                    options: Vec::new(), option: String::new(), unquoted: String::new(),
+                    quoted: false, special: false, text: false, next: false, over: false}
             }
 
             fn update(&mut self, c: char)
@@ -128,19 +635,17 @@ pub mod csv_reader
 
                 match c
                 {
-                    '"' =>
+                    c if c==self.quote =>
                     {
                         self.text = !self.text;
+                        self.quoted = true;
                         self.special = true;
                     },
-                    ',' =>
+                    c if c==self.delimiter && !self.text =>
                     {
-                        if !self.text
-                        {
-                            self.over = true;
-                        }
+                        self.over = true;
                     },
-                    ';' =>
+                    c if c==self.separator && !self.text =>
                     {
                         self.next = true;
                         self.special = true;
@@ -151,45 +656,194 @@ pub mod csv_reader
 
             fn parse(&mut self, c: char) -> Option<Vec<String>>
             {
-                if self.text && !self.special
+                if !self.special
                 {
-                    self.option.push(c);
+                    if self.text
+                    {
+                        self.option.push(c);
+                    } else if !self.over
+                    {
+                        self.unquoted.push(c);
+                    }
                 }
 
                 if self.next || self.over
                 {
-                    self.options.push(mem::replace(&mut self.option, String::new()));
+                    let unquoted = mem::take(&mut self.unquoted);
+
+                    // text outside of quotes only counts if the choice wasnt quoted
+                    if !mem::take(&mut self.quoted)
+                    {
+                        self.option = unquoted.trim().to_string();
+                    }
+
+                    self.options.push(mem::take(&mut self.option));
                 }
 
                 if self.over
                 {
-                    return Some(mem::replace(&mut self.options, Vec::new()));
+                    return Some(mem::take(&mut self.options));
                 }
 
                 None
             }
         }
 
-        pub fn parse(file: &str) -> Result<Answers, &'static str>
+        #[allow(clippy::suspicious_else_formatting)]
+        pub fn parse(
+            file: &str,
+            options: &ParseOptions,
+            start: Instant
+        ) -> Result<(Answers, ParseStats), String>
         {
-            let lines = split_lines(&file);
-            let mut lines = lines.iter();
+            let numbered_lines = if options.widths.is_some()
+            {
+                file.lines().enumerate().map(|(index, line)| (index+1, line)).collect()
+            } else
+            {
+                split_lines(file, options.quote)
+            };
+
+            let read = numbered_lines.len();
+
+            let mut lines = Vec::new();
+            for (number, line) in numbered_lines
+            {
+                if line.trim().is_empty()
+                {
+                    if options.allow_blank_lines
+                    {
+                        continue;
+                    }
+
+                    return Err(format!("blank line at line {number}"));
+                }
+
+                lines.push(line);
+            }
 
-            let labels = parse_line(lines.next().ok_or("first line missing")?)
-                .into_iter().flatten().collect();
+            let kept = lines.len();
+            let mut lines = lines.into_iter().peekable();
+
+            let labels = if options.has_header
+            {
+                tokenize(lines.next().ok_or("first line missing")?, options)
+                    .into_iter().flatten().collect()
+            } else
+            {
+                let first = lines.peek().ok_or("first line missing")?;
+                numbered_labels(tokenize(first, options).len())
+            };
 
             let mut replies: Vec<Reply> = Vec::new();
-            for line in lines
+            for (index, line) in lines.enumerate()
+            {
+                // checking the clock every row would slow down parsing
+                if index%TIMEOUT_CHECK==0
+                {
+                    check_timeout(options, start, index+1)?;
+                }
+
+                replies.push(tokenize(line, options));
+            }
+
+            let stats = ParseStats{lines: read, skipped: read-kept, rows: replies.len()};
+
+            Ok((Answers{labels, replies}, stats))
+        }
+
+        pub fn check_timeout(options: &ParseOptions, start: Instant, row: usize) -> Result<(), String>
+        {
+            match options.timeout
+            {
+                Some(timeout) if start.elapsed()>timeout =>
+                {
+                    Err(format!("timed out after {:.2}s on row {row}", timeout.as_secs_f64()))
+                },
+                _ => Ok(())
+            }
+        }
+
+        pub fn numbered_labels(amount: usize) -> Vec<String>
+        {
+            (0..amount).map(|index| index.to_string()).collect()
+        }
+
+        // reads the replies one record at a time without holding the whole file
+        pub fn records<R: BufRead>(
+            reader: R,
+            options: &ParseOptions) -> impl Iterator<Item=Result<Reply, String>>
+        {
+            let options = options.clone();
+            let mut lines = reader.lines();
+            let mut number = 0;
+
+            std::iter::from_fn(move ||
+            {
+                let mut record = String::new();
+                loop
+                {
+                    number += 1;
+                    let line = match lines.next()
+                    {
+                        Some(Ok(line)) => line,
+                        Some(Err(error)) => return Some(Err(format!("{error}"))),
+                        None if record.trim().is_empty() => return None,
+                        None => return Some(Ok(tokenize(&record, &options)))
+                    };
+
+                    if !record.is_empty()
+                    {
+                        record.push('\n');
+                    }
+
+                    record.push_str(strip_carriage(&line));
+
+                    let inside_quote = options.widths.is_none()
+                        && record.chars().filter(|c| *c==options.quote).count()%2==1;
+
+                    if !inside_quote
+                    {
+                        if record.trim().is_empty()
+                        {
+                            if !options.allow_blank_lines
+                            {
+                                return Some(Err(format!("blank line at line {number}")));
+                            }
+
+                            record.clear();
+                            continue;
+                        }
+
+                        return Some(Ok(tokenize(&record, &options)));
+                    }
+                }
+            })
+        }
+
+        fn tokenize(input: &str, options: &ParseOptions) -> Reply
+        {
+            match &options.widths
             {
-                replies.push(parse_line(&line));
+                Some(widths) => parse_fixed(input, widths),
+                None => parse_line(input, options)
             }
+        }
 
-            Ok(Answers{labels, replies})
+        fn parse_fixed(input: &str, widths: &[usize]) -> Reply
+        {
+            let mut chars = input.chars();
+
+            widths.iter().map(|width|
+            {
+                let field: String = chars.by_ref().take(*width).collect();
+                vec![field.trim_end().to_string()]
+            }).collect()
         }
 
-        fn parse_line(input: &str) -> Reply
+        fn parse_line(input: &str, options: &ParseOptions) -> Reply
         {
-            let mut state = State::new();
+            let mut state = State::new(options);
 
             let mut line: Reply = Vec::new();
             for c in input.chars()
@@ -201,39 +855,53 @@ pub mod csv_reader
                 }
             }
 
-            state.update(',');
-            line.push(state.parse(',').expect("always returns string after comma"));
+            state.update(options.delimiter);
+            line.push(state.parse(options.delimiter).expect("always returns string after delimiter"));
 
             line
         }
 
-        fn split_lines<'a>(file: &'a str) -> Vec<&'a str>
+        // pairs every line with the line number it starts at
+        fn split_lines(file: &str, quote: char) -> Vec<(usize, &str)>
         {
             let mut text = false;
             let mut last_pushed = 0;
 
+            let mut number = 1;
+            let mut line_start = 1;
+
             let mut out = Vec::new();
-            for (index, c) in file.bytes().enumerate()
+            for (index, c) in file.char_indices()
             {
                 match c
                 {
-                    b'"' => text = !text,
-                    b'\n' =>
+                    c if c==quote => text = !text,
+                    '\n' if !text =>
                     {
-                        if !text
-                        {
-                            out.push(&file[last_pushed..index]);
-                            last_pushed = index;
-                        }
+                        out.push((line_start, strip_carriage(&file[last_pushed..index])));
+                        last_pushed = index+1;
+
+                        number += 1;
+                        line_start = number;
                     },
+                    '\n' => number += 1,
                     _ => ()
                 }
             }
-            out.push(&file[last_pushed..]);
+
+            if last_pushed<file.len()
+            {
+                out.push((line_start, strip_carriage(&file[last_pushed..])));
+            }
 
             out
         }
 
+        fn strip_carriage(line: &str) -> &str
+        {
+            line.strip_suffix('\r').unwrap_or(line)
+        }
+
         #[cfg(test)]
         mod tests
         {
@@ -243,23 +911,48 @@ pub mod csv_reader
             fn parse_line()
             {
                 let result = parser::parse_line(
-                    "\"Thingy 🥺\", \"Dingy 🥺\", \"Test!!ъ\", \"one;two\"");
+                    "\"Thingy 🥺\", \"Dingy 🥺\", \"Test!!ъ\", \"one;two\", one;two",
+                    &ParseOptions::default());
 
                 assert_eq!(result,
                     vec![
                         vec!["Thingy 🥺"],
                         vec!["Dingy 🥺"],
                         vec!["Test!!ъ"],
+                        vec!["one;two"],
                         vec!["one", "two"]
                         ]);
             }
 
+            #[test]
+            fn spaces_outside_quotes()
+            {
+                let options = ParseOptions::default();
+
+                assert_eq!(parser::parse_line(" \"a\" , \"b\" ", &options), vec![vec!["a"], vec!["b"]]);
+                assert_eq!(parser::parse_line("\t\" a \"\t,  \"b;c\"", &options), vec![vec![" a "], vec!["b;c"]]);
+                assert_eq!(parser::parse_line(" \"a\" ; b , c ", &options), vec![vec!["a", "b"], vec!["c"]]);
+            }
+
+            #[test]
+            fn parse_from_reader()
+            {
+                let file = "\"q1\", \"q2\"\n\"a\", \"multi\nline\"\n\"b\", c;d\n";
+
+                let options = ParseOptions::default();
+                let answers = Answers::parse_from(std::io::Cursor::new(file.as_bytes().to_vec()), &options);
+                assert_eq!(answers, Answers::parse(file));
+
+                let invalid = std::io::Cursor::new(vec![b'"', 0xff, b'"']);
+                assert!(Answers::parse_from(invalid, &options).is_err());
+            }
+
             #[test]
             fn parse_full()
             {
                 let result = Answers::parse(
                     "\"q1 🥺\", \"q2 wowie\", \"q3 ok\"
-                    \"yea\", \"yea;no\", \"yea\"
+                    \"yea\", yea;no, \"yea\"
                     \"what\", \"sure\", \"mhmm\"");
 
                 assert_eq!(result, Ok(Answers
@@ -280,6 +973,420 @@ pub mod csv_reader
                             ]]
                 }));
             }
+
+            #[test]
+            fn parse_with_options()
+            {
+                let options = ParseOptions{delimiter: '\t', separator: '|', quote: '\'',
+                    has_header: false, ..Default::default()};
+
+                let result = Answers::parse_with("a|b\t'x, y|z'\n\tc", &options);
+
+                assert_eq!(result, Ok(Answers
+                {
+                    labels: vec![String::from("0"), String::from("1")],
+                    replies: vec![
+                        vec![
+                            vec![String::from("a"), String::from("b")],
+                            vec![String::from("x, y|z")]
+                            ],
+                        vec![
+                            vec![String::from("")],
+                            vec![String::from("c")]
+                            ]]
+                }));
+            }
+
+            #[test]
+            fn parse_fixed_width()
+            {
+                let options = ParseOptions{widths: Some(vec![6, 10, 4]), ..Default::default()};
+
+                let result = Answers::parse_with(
+                    "name  city      age\n\
+                    tom   new york  31\r\n\
+                    ann   \"riga\"    7\n\
+                    bob", &options);
+
+                assert_eq!(result, Ok(Answers
+                {
+                    labels: vec![String::from("name"), String::from("city"), String::from("age")],
+                    replies: vec![
+                        vec![
+                            vec![String::from("tom")],
+                            vec![String::from("new york")],
+                            vec![String::from("31")]
+                            ],
+                        vec![
+                            vec![String::from("ann")],
+                            vec![String::from("\"riga\"")],
+                            vec![String::from("7")]
+                            ],
+                        vec![
+                            vec![String::from("bob")],
+                            vec![String::new()],
+                            vec![String::new()]
+                            ]]
+                }));
+            }
+
+            #[test]
+            fn require_rectangular()
+            {
+                let file = "\"q1\", \"q2\"\n\"a\", \"b\"\n\"c\"\n\"d\", \"e\"\n\"f\", \"g\", \"h\"";
+
+                assert_eq!(Answers::parse(file),
+                    Err(String::from("replies are not the same size as labels")));
+
+                let options = ParseOptions{require_rectangular: true, ..Default::default()};
+                assert_eq!(Answers::parse_with(file, &options),
+                    Err(String::from("row 2 has 1 fields, row 4 has 3 fields, expected 2")));
+
+                let file = "\"q1\", \"q2\"\n\"a\", \"b\"";
+                assert!(Answers::parse_with(file, &options).is_ok());
+            }
+
+            #[test]
+            fn timeout()
+            {
+                let mut file = String::from("\"q1\", \"q2\"");
+                for index in 0..20000
+                {
+                    file.push_str(&format!("\n\"{index}\", a;b;c"));
+                }
+
+                let options = ParseOptions{timeout: Some(Duration::from_nanos(1)), ..Default::default()};
+                let error = Answers::parse_with(&file, &options).unwrap_err();
+                assert!(error.starts_with("timed out after"), "{error}");
+
+                let options = ParseOptions{timeout: Some(Duration::from_secs(60)), ..Default::default()};
+                assert_eq!(Answers::parse_with(&file, &options).unwrap().replies().len(), 20000);
+            }
+
+            #[test]
+            fn stream_timeout()
+            {
+                let mut file = String::from("\"q1\", \"q2\"");
+                for index in 0..20000
+                {
+                    file.push_str(&format!("\n\"{index}\", a;b;c"));
+                }
+
+                let stream = |options: &ParseOptions|
+                {
+                    stream_frequencies(file.as_bytes(), options, |label| label=="q2")
+                };
+
+                let options = ParseOptions{timeout: Some(Duration::from_nanos(1)), ..Default::default()};
+                let error = stream(&options).unwrap_err();
+                assert!(error.starts_with("timed out after"), "{error}");
+
+                let options = ParseOptions{timeout: Some(Duration::from_secs(60)), ..Default::default()};
+                assert_eq!(stream(&options).unwrap().1.get("a"), Some(&20000));
+            }
+
+            #[test]
+            fn parse_stats()
+            {
+                let file = "\"q1\", \"q2\"\n\n\"a\", \"multi\nline\"\n   \n\"b\", \"c\"\n\r\n\"d\", \"e\"\n";
+
+                let (answers, stats) = Answers::parse_with_stats(file, &ParseOptions::default()).unwrap();
+                assert_eq!(stats, ParseStats{lines: 7, skipped: 3, rows: 3});
+                assert_eq!(answers.replies().len(), 3);
+
+                let options = ParseOptions{has_header: false, ..Default::default()};
+                let (_, stats) = Answers::parse_with_stats(file, &options).unwrap();
+                assert_eq!(stats, ParseStats{lines: 7, skipped: 3, rows: 4});
+            }
+
+            #[test]
+            fn blank_lines()
+            {
+                let file = "\"q1\"\n\"a\"\n\n\"b\"\n";
+
+                let answers = Answers::parse(file).unwrap();
+                assert_eq!(answers.question("q1"), Some(vec!["q1", "a", "b"]));
+
+                let options = ParseOptions{allow_blank_lines: false, ..Default::default()};
+                assert_eq!(Answers::parse_with(file, &options),
+                    Err(String::from("blank line at line 3")));
+
+                let multiline = "\"q1\"\n\"a\nb\"\n   \n\"c\"";
+                assert_eq!(Answers::parse_with(multiline, &options),
+                    Err(String::from("blank line at line 4")));
+            }
+
+            #[test]
+            fn dedupe_by()
+            {
+                let mut answers = Answers::parse(
+                    "\"uid\", \"score\"
+                    \"tom\", \"1\"
+                    \"ann\", \"2\"
+                    \"tom\", \"3\"
+                    \"\", \"4\"
+                    \"\", \"5\"
+                    \"bob\", \"6\"").unwrap();
+
+                answers.dedupe_by(0);
+                assert_eq!(answers.question("uid"), Some(vec!["uid", "ann", "tom", "", "", "bob"]));
+                assert_eq!(answers.question("score"), Some(vec!["score", "2", "3", "4", "5", "6"]));
+            }
+
+            #[test]
+            fn map_column()
+            {
+                let mut answers = Answers::parse(
+                    "\"name\", \"city\"
+                    \"tom\", \"riga\"
+                    \"ann\", oslo;bergen").unwrap();
+
+                answers.map_column("city", |city| city.to_uppercase()).unwrap();
+
+                assert_eq!(answers.question("city"), Some(vec!["city", "RIGA", "OSLO", "BERGEN"]));
+                assert_eq!(answers.question("name"), Some(vec!["name", "tom", "ann"]));
+
+                assert!(answers.map_column("country", |country| country.to_string()).is_err());
+            }
+
+            #[test]
+            fn collapse_whitespace()
+            {
+                let mut answers = Answers::parse(
+                    "\"city\"
+                    \"new york\"
+                    \" new   york \"
+                    \"new\tyork\"").unwrap();
+
+                answers.collapse_whitespace();
+                assert_eq!(answers.question("city"),
+                    Some(vec!["city", "new york", "new york", "new york"]));
+            }
+
+            #[test]
+            fn dedupe_choices()
+            {
+                let mut answers = Answers::parse(
+                    "\"pets\", \"other\"
+                    cat;dog;cat, \"cat\"
+                    dog;dog, \"cat\"").unwrap();
+
+                answers.dedupe_choices();
+                assert_eq!(answers.question("pets"), Some(vec!["pets", "cat", "dog", "dog"]));
+                assert_eq!(answers.question("other"), Some(vec!["other", "cat", "cat"]));
+            }
+
+            #[test]
+            fn retain_indices()
+            {
+                let mut answers = Answers::parse("\"q\"\n\"a\"\n\"b\"\n\"c\"\n\"d\"").unwrap();
+
+                answers.retain_indices(&[3, 1, 7]);
+                assert_eq!(answers.question("q"), Some(vec!["q", "b", "d"]));
+            }
+
+            #[test]
+            fn sort_respondents_by()
+            {
+                let mut answers = Answers::parse(
+                    "\"uid\", \"q\"\n\"c\", \"1\"\n\"a\", \"2\"\n\"\", \"3\"\n\"b\", \"4\"\n\"a\", \"5\""
+                ).unwrap();
+
+                answers.sort_respondents_by("uid").unwrap();
+                assert_eq!(answers.question("q"), Some(vec!["q", "3", "2", "5", "4", "1"]));
+
+                assert_eq!(answers.sort_respondents_by("missing"), Err(String::from("cant find missing")));
+
+                let mut answers = Answers::parse(
+                    "\"score\", \"q\"\n\"10\", \"a\"\n\"9\", \"b\"\n\"n/a\", \"c\"\n\"100\", \"d\"\n\"-2.5\", \"e\"\n\"$20\", \"f\""
+                ).unwrap();
+
+                answers.sort_respondents_by("score").unwrap();
+                assert_eq!(answers.question("q"), Some(vec!["q", "e", "b", "a", "d", "f", "c"]));
+
+                let dollars = |text: &str| text.trim_start_matches('$').parse().ok();
+                answers.sort_respondents_with("score", dollars).unwrap();
+                assert_eq!(answers.question("q"), Some(vec!["q", "e", "b", "a", "f", "d", "c"]));
+            }
+
+            #[test]
+            fn weighted_choices()
+            {
+                assert_eq!(split_weight("a b : 4"), Ok(("a b", 4)));
+                assert_eq!(split_weight("optionC:0"), Ok(("optionC", 0)));
+                assert_eq!(split_weight("no weight"), Ok(("no weight", 1)));
+                assert_eq!(split_weight("ratio 1:x"), Ok(("ratio 1:x", 1)));
+                assert_eq!(split_weight("a:"), Ok(("a:", 1)));
+                assert_eq!(split_weight("a:-3"), Ok(("a:-3", 1)));
+                assert_eq!(split_weight("a:4000000000"), Ok(("a", 4_000_000_000)));
+
+                assert!(split_weight("a:99999999999").is_err());
+            }
+
+            #[test]
+            fn to_csv()
+            {
+                let answers = Answers::parse(
+                    "\"name, full\", \"pets\", \"note\"
+                    \"  ann \", cat;dog, \"\"
+                    bob, \"fish\", \"a;b\"").unwrap();
+
+                let csv = answers.to_csv();
+                assert_eq!(csv, "\"name, full\",pets,note\n\"  ann \",cat;dog,\"\"\nbob,fish,\"a;b\"\n");
+                assert_eq!(Answers::parse(&csv), Ok(answers));
+
+                assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+            }
+
+            #[test]
+            fn column_type()
+            {
+                let answers = Answers::parse(
+                    "\"age\", \"color\", \"comment\"
+                    \"21\", \"red\", \"great stuff\"
+                    \"35\", \"blue\", \"too long\"
+                    \"40.5\", \"red\", \"\"
+                    \"\", \"red\", \"would do again\"
+                    \"19\", \"blue\", \"meh\"
+                    \"22\", \"red\", \"loved it\"
+                    \"30\", \"blue\", \"nope\"
+                    \"28\", \"red\", \"fine\"
+                    \"33\", \"red\", \"ok i guess\"
+                    \"27\", \"blue\", \"good\"
+                    \"n/a\", \"red\", \"bad\"").unwrap();
+
+                assert_eq!(answers.column_type("age"), Some(ColumnType::Numeric));
+                assert_eq!(answers.column_type("color"), Some(ColumnType::Categorical));
+                assert_eq!(answers.column_type("comment"), Some(ColumnType::Text));
+                assert_eq!(answers.column_type("missing"), None);
+            }
+
+            #[test]
+            fn distinct_values()
+            {
+                let answers = Answers::parse(
+                    "\"color\", \"size\"
+                    \"red\", \"s\"
+                    blue;red, \"m\"
+                    \"\", \"s\"
+                    \"green\", \"l\"").unwrap();
+
+                assert_eq!(answers.distinct_values("color"), Some(vec!["blue", "green", "red"]));
+                assert_eq!(answers.distinct_values("size"), Some(vec!["l", "m", "s"]));
+                assert_eq!(answers.distinct_values("colo"), None);
+            }
+
+            #[test]
+            fn validate_numeric()
+            {
+                let answers = Answers::parse(
+                    "\"age\"
+                    \"21\"
+                    \"twenty\"
+                    \" 3.5 \"
+                    \"\"
+                    \"1o\"
+                    \"twenty\"
+                    \"-4\"").unwrap();
+
+                assert_eq!(answers.validate_numeric("age"), Some(vec!["1o", "twenty"]));
+                assert_eq!(answers.validate_numeric("ag"), None);
+            }
+
+            #[test]
+            fn answer_lengths()
+            {
+                let answers = Answers::parse(
+                    "\"time\", \"comments\"
+                    \"1\", \"too long\"
+                    \"2\", \"\"
+                    \"3\", \"ok\"
+                    \"4\", \"naïve 🥺\"").unwrap();
+
+                assert_eq!(answers.answer_lengths("comments"), Some(vec![8, 2, 7]));
+                assert_eq!(answers.answer_lengths("comment"), None);
+            }
+
+            #[test]
+            fn whitespace_blanks()
+            {
+                let mut answers = Answers::parse(
+                    "\"team\", \"comment\"
+                    \"red\", \"ok\"
+                    \"  \", \"   \"
+                    \"red\", \"fine\"
+                    \" \", \"\t\"").unwrap();
+
+                assert_eq!(answers.distinct_values("comment"), Some(vec!["fine", "ok"]));
+                assert_eq!(answers.answer_lengths("comment"), Some(vec![2, 4]));
+                assert_eq!(answers.group_by(0, 1), vec![("red", vec!["ok", "fine"])]);
+
+                answers.dedupe_by(0);
+                assert_eq!(answers.question("comment"), Some(vec!["comment", "   ", "fine", "\t"]));
+            }
+
+            #[test]
+            fn mixed_line_endings()
+            {
+                let unix = "\"q1\", \"q2\"\n\"a\", \"b\"\n\"c\", \"d\"\n\"e\nf\", \"g\"\n";
+                let mixed = "\"q1\", \"q2\"\r\n\"a\", \"b\"\n\"c\", \"d\"\r\n\"e\nf\", \"g\"\r\n";
+
+                assert_eq!(Answers::parse(mixed), Answers::parse(unix));
+
+                let lines: Vec<&str> = parser::split_lines(mixed, '"').into_iter()
+                    .map(|(_, line)| line)
+                    .collect();
+
+                assert!(lines.iter().all(|line| !line.ends_with('\r')));
+            }
+
+            #[test]
+            fn collect_order()
+            {
+                let answers = Answers::parse(
+                    "\"q1\", \"q2\"
+                    a;b;c, \"x\"
+                    \"\", \"y\"
+                    d, e;f").unwrap();
+
+                assert_eq!(answers.collect(0), vec!["q1", "a", "b", "c", "", "d"]);
+                assert_eq!(answers.collect(1), vec!["q2", "x", "y", "e", "f"]);
+            }
+
+            #[test]
+            fn question_by_index()
+            {
+                let answers = Answers::parse(
+                    "\"q1\", \"q2\", \"time\"
+                    \"a\", \"b\", \"12:00\"").unwrap();
+
+                assert_eq!(answers.question_by_index(0), Some(vec!["q1", "a"]));
+                assert_eq!(answers.question_by_index(-1), Some(vec!["time", "12:00"]));
+                assert_eq!(answers.question_by_index(-3), Some(vec!["q1", "a"]));
+
+                assert_eq!(answers.question_by_index(3), None);
+                assert_eq!(answers.question_by_index(-4), None);
+            }
+
+            #[test]
+            fn retain_range()
+            {
+                let mut answers = Answers::parse(
+                    "\"q1\"
+                    \"a\"
+                    \"b\"
+                    \"c\"
+                    \"d\"").unwrap();
+
+                answers.retain_range(1, 3);
+                assert_eq!(answers.question("q1"), Some(vec!["q1", "b", "c"]));
+
+                answers.retain_range(1, 100);
+                assert_eq!(answers.question("q1"), Some(vec!["q1", "c"]));
+
+                answers.retain_range(5, 10);
+                assert_eq!(answers.question("q1"), Some(vec!["q1"]));
+            }
         }
     }
 }
\ No newline at end of file
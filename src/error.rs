@@ -0,0 +1,48 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error
+{
+    // bad command line arguments
+    Config(String),
+    Io(io::Error),
+    // the file isnt valid csv for the given parse options
+    Parse(String),
+    // the file parsed but cant be analyzed the way it was asked
+    Analysis(String)
+}
+
+impl fmt::Display for Error
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Error::Config(text) => write!(f, "{text}"),
+            Error::Io(error) => write!(f, "{error}"),
+            Error::Parse(text) => write!(f, "parse error: {text}"),
+            Error::Analysis(text) => write!(f, "{text}")
+        }
+    }
+}
+
+impl std::error::Error for Error
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+    {
+        match self
+        {
+            Error::Io(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for Error
+{
+    fn from(error: io::Error) -> Self
+    {
+        Error::Io(error)
+    }
+}
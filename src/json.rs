@@ -0,0 +1,52 @@
+// just enough json writing for the outputs that need it
+
+pub fn string(text: &str) -> String
+{
+    let mut out = String::from("\"");
+    for c in text.chars()
+    {
+        match c
+        {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() && u32::from(c)<0x20 => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+
+    out
+}
+
+pub fn array(values: impl Iterator<Item=String>) -> String
+{
+    format!("[{}]", values.collect::<Vec<String>>().join(","))
+}
+
+// keys are kept in the given order
+pub fn object<'a>(fields: impl Iterator<Item=(&'a str, String)>) -> String
+{
+    let fields: Vec<String> = fields.map(|(key, value)| format!("{}:{value}", string(key)))
+        .collect();
+
+    format!("{{{}}}", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn escaping()
+    {
+        assert_eq!(string("say \"hi\"\\\n\u{1}🥺"), "\"say \\\"hi\\\"\\\\\\n\\u0001🥺\"");
+
+        let values = ["a", "b"].into_iter().map(string);
+        assert_eq!(object([("list", array(values)), ("x", string("y"))].into_iter()),
+            "{\"list\":[\"a\",\"b\"],\"x\":\"y\"}");
+    }
+}
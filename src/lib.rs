@@ -1,7 +1,22 @@
-use std::error::Error;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, BTreeMap};
 use std::cmp::Ordering;
+use std::hash::Hash;
+use std::borrow::Cow;
+use std::iter;
 use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::io::{self, BufRead, BufReader, Read};
+
+use flate2::bufread::GzDecoder;
+use regex::Regex;
+
+const STATS: [&str; 5] = ["average", "median", "iqr", "std_dev", "cv"];
+const BUCKETS: [&str; 3] = ["day", "month", "year"];
+const STOPWORDS: [&str; 20] = [
+    "a", "an", "and", "are", "as", "at", "be", "but", "for", "in",
+    "is", "it", "of", "on", "or", "that", "the", "to", "was", "with"
+];
 
 pub struct Config
 {
@@ -10,12 +25,86 @@ pub struct Config
     rank: bool,
     unique: bool,
     exact: bool,
-    mappings: HashMap<String, i32>
+    mappings: HashMap<String, i32>,
+    range: Option<(usize, usize)>,
+    report: Option<String>,
+    column: Option<isize>,
+    parse_options: ParseOptions,
+    breakdown: Option<String>,
+    collapse_whitespace: bool,
+    shell: bool,
+    required_columns: Vec<String>,
+    state: Option<String>,
+    oneline: bool,
+    uid_column: Option<usize>,
+    group_by: Option<String>,
+    dedupe_uid: bool,
+    list_columns: bool,
+    reversed: Vec<String>,
+    score_histogram: bool,
+    assert_average: Option<(String, f64, f64)>,
+    stream: bool,
+    duration_column: Option<String>,
+    min_duration: Option<f64>,
+    min_average: Option<f64>,
+    avg_selections: bool,
+    fail_on_empty: bool,
+    stats: Vec<String>,
+    bayesian_rank: bool,
+    prior: Option<f64>,
+    sanitize: bool,
+    mapping_report: bool,
+    trend: Option<String>,
+    text_stats: bool,
+    word_freq: Option<usize>,
+    skip_stopwords: bool,
+    dedupe_answers: bool,
+    bootstrap: Option<usize>,
+    seed: Option<u64>,
+    balance: Option<String>,
+    per_group: Option<usize>,
+    validate_numeric: bool,
+    stamp: bool,
+    merge_separator: Option<String>,
+    crosstab: Option<String>,
+    crosstab_out: Option<String>,
+    scale: Vec<String>,
+    cumulative: bool,
+    cohort: Option<String>,
+    respondents_jsonl: bool,
+    jaccard: Option<(String, String)>,
+    count_per_respondent: bool,
+    expected: Vec<(String, f64)>,
+    chi_square: bool,
+    index_prefix: bool,
+    loose_numbers: bool,
+    parse_stats: bool,
+    sort_by: Option<String>,
+    nps: bool,
+    boxplot: bool,
+    mapping_coverage: bool,
+    weight: Option<String>,
+    json_schema: bool,
+    json: bool,
+    max_categories: Option<usize>,
+    weighted_choices: bool,
+    collate: Option<String>,
+    top_percent: Option<f64>,
+    columns_regex: Option<Regex>,
+    bucket: String,
+    date_column: Option<String>,
+    compare_groups: Option<(String, String, String)>
 }
 
 impl Config
 {
-    pub fn build(args: impl Iterator<Item = String>) -> Result<Self, String>
+    pub fn build(args: impl Iterator<Item = String>) -> Result<Self, Error>
+    {
+        Self::parse_args(args).map_err(Error::Config)
+    }
+
+    #[allow(clippy::suspicious_else_formatting)]
+    fn parse_args(args: impl Iterator<Item = String>) -> Result<Self, String>
     {
         let mut filepath: Option<String> = None;
         let mut search = String::new();
@@ -23,13 +112,85 @@ impl Config
         let mut rank = false;
         let mut unique = false;
         let mut exact = false;
+        let mut collapse_whitespace = false;
+        let mut shell = false;
+        let mut oneline = false;
+        let mut uid_first = false;
+        let mut dedupe_uid = false;
+        let mut list_columns = false;
+        let mut score_histogram = false;
+        let mut stream = false;
+        let mut avg_selections = false;
+        let mut fail_on_empty = false;
+        let mut bayesian_rank = false;
+        let mut sanitize = false;
+        let mut mapping_report = false;
+        let mut text_stats = false;
+        let mut skip_stopwords = false;
+        let mut dedupe_answers = false;
+        let mut validate_numeric = false;
+        let mut stamp = false;
+        let mut cumulative = false;
+        let mut respondents_jsonl = false;
+        let mut count_per_respondent = false;
+        let mut chi_square = false;
+        let mut index_prefix = false;
+        let mut loose_numbers = false;
+        let mut parse_stats = false;
+        let mut sort_by: Option<String> = None;
+        let mut nps = false;
+        let mut boxplot = false;
+        let mut mapping_coverage = false;
+        let mut weight: Option<String> = None;
+        let mut json_schema = false;
+        let mut json = false;
+        let mut max_categories: Option<usize> = None;
+        let mut weighted_choices = false;
+        let mut collate: Option<String> = None;
+        let mut top_percent: Option<f64> = None;
 
         let mut mappings: HashMap<String, i32> = HashMap::new();
+        let mut range: Option<(usize, usize)> = None;
+        let mut report: Option<String> = None;
+        let mut column: Option<isize> = None;
+        let mut breakdown: Option<String> = None;
+        let mut required_columns: Vec<String> = Vec::new();
+        let mut reversed: Vec<String> = Vec::new();
+        let mut state: Option<String> = None;
+        let mut uid_column: Option<usize> = None;
+        let mut group_by: Option<String> = None;
+        let mut compare_groups: Option<(String, String, String)> = None;
+        let mut assert_average: Option<(String, f64, f64)> = None;
+        let mut duration_column: Option<String> = None;
+        let mut min_duration: Option<f64> = None;
+        let mut min_average: Option<f64> = None;
+        let mut prior: Option<f64> = None;
+        let mut trend: Option<String> = None;
+        let mut word_freq: Option<usize> = None;
+        let mut bootstrap: Option<usize> = None;
+        let mut seed: Option<u64> = None;
+        let mut balance: Option<String> = None;
+        let mut per_group: Option<usize> = None;
+        let mut merge_separator: Option<String> = None;
+        let mut crosstab: Option<String> = None;
+        let mut crosstab_out: Option<String> = None;
+        let mut scale: Vec<String> = Vec::new();
+        let mut scale_values: HashMap<String, i32> = HashMap::new();
+        let mut cohort: Option<String> = None;
+        let mut jaccard: Option<(String, String)> = None;
+        let mut columns_regex: Option<Regex> = None;
+        let mut expected: Vec<(String, f64)> = Vec::new();
+        let mut bucket = String::from("month");
+        let mut date_column: Option<String> = None;
+        let mut stats: Vec<String> = vec![String::from("average"), String::from("median")];
+
+        let mut parse_options = ParseOptions::default();
 
         let mut args = args.peekable();
         while let Some(arg) = args.next()
         {
-            if args.peek().is_none()
+            // the schema can be asked for without a file
+            if args.peek().is_none() && arg!="--json-schema"
             {
                 filepath = Some(arg);
                 continue;
@@ -45,24 +206,387 @@ impl Config
                     mappings = Self::parse_mappings(&mapping)?;
                 },
 
+                "--range" =>
+                {
+                    let text = args.next().ok_or("no range")?;
+                    range = Some(Self::parse_range(&text)?);
+                },
+
+                "--report" => report = Some(args.next().ok_or("no report path")?),
+
+                "--state" => state = Some(args.next().ok_or("no state path")?),
+
+                "--col" =>
+                {
+                    let index = args.next().ok_or("no column index")?;
+                    column = Some(index.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--uid-col" =>
+                {
+                    let index = args.next().ok_or("no uid column index")?;
+                    uid_column = Some(index.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--group-by" => group_by = Some(args.next().ok_or("no group column")?),
+
+                "--assert-average" =>
+                {
+                    let assertion = args.next().ok_or("no average assertion")?;
+                    assert_average = Some(Self::parse_average_assertion(&assertion)?);
+                },
+
+                "--word-freq" =>
+                {
+                    let amount = args.next().ok_or("no word amount")?;
+                    word_freq = Some(amount.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--max-categories" =>
+                {
+                    let amount = args.next().ok_or("no category amount")?;
+                    max_categories = Some(amount.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--bootstrap" =>
+                {
+                    let iterations = args.next().ok_or("no bootstrap iterations")?;
+                    bootstrap = Some(iterations.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--seed" =>
+                {
+                    let number = args.next().ok_or("no seed")?;
+                    seed = Some(number.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--scale" =>
+                {
+                    let points = args.next().ok_or("no scale")?;
+                    (scale, scale_values) = Self::parse_scale(&points)?;
+                },
+
+                "--jaccard" =>
+                {
+                    let questions = args.next().ok_or("no jaccard questions")?;
+                    let (first, second) = questions.split_once(',')
+                        .ok_or("jaccard questions should be <question>,<question>")?;
+
+                    jaccard = Some((first.to_string(), second.to_string()));
+                },
+
+                "--expected" =>
+                {
+                    let proportions = args.next().ok_or("no expected proportions")?;
+                    expected = Self::parse_expected(&proportions)?;
+                },
+
+                "--columns-regex" =>
+                {
+                    let pattern = args.next().ok_or("no columns pattern")?;
+                    columns_regex = Some(Regex::new(&pattern).map_err(|error| format!("{error}"))?);
+                },
+
+                "--cohort" => cohort = Some(args.next().ok_or("no cohort question")?),
+                "--weight" => weight = Some(args.next().ok_or("no weight column")?),
+                "--json-schema" => json_schema = true,
+                "--json" => json = true,
+
+                "--crosstab" => crosstab = Some(args.next().ok_or("no crosstab question")?),
+
+                "--crosstab-out" => crosstab_out = Some(args.next().ok_or("no crosstab path")?),
+
+                "--merge-sep" => merge_separator = Some(args.next().ok_or("no merge separator")?),
+
+                "--balance" => balance = Some(args.next().ok_or("no balance column")?),
+
+                "--per-group" =>
+                {
+                    let amount = args.next().ok_or("no per group amount")?;
+                    per_group = Some(amount.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--trend" => trend = Some(args.next().ok_or("no trend question")?),
+
+                "--by" =>
+                {
+                    let name = args.next().ok_or("no date bucket")?;
+                    bucket = Self::parse_bucket(&name)?;
+                },
+
+                "--date-col" => date_column = Some(args.next().ok_or("no date column")?),
+
+                "--duration-col" => duration_column = Some(args.next().ok_or("no duration column")?),
+
+                "--min-duration" =>
+                {
+                    let seconds = args.next().ok_or("no minimum duration")?;
+                    min_duration = Some(seconds.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--min-average" =>
+                {
+                    let average = args.next().ok_or("no minimum average")?;
+                    min_average = Some(average.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--top-percent" =>
+                {
+                    let percent = args.next().ok_or("no top percentage")?;
+                    top_percent = Some(percent.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--stats" =>
+                {
+                    let names = args.next().ok_or("no stats")?;
+                    stats = Self::parse_stats(&names)?;
+                },
+
+                "--prior" =>
+                {
+                    let weight = args.next().ok_or("no prior weight")?;
+                    prior = Some(weight.parse().map_err(|error| format!("{error}"))?);
+                },
+
+                "--compare-groups" =>
+                {
+                    let comparison = args.next().ok_or("no group comparison")?;
+                    compare_groups = Some(Self::parse_comparison(&comparison)?);
+                },
+
+                "--breakdown" => breakdown = Some(args.next().ok_or("no breakdown value")?),
+
+                "--require-column" =>
+                {
+                    required_columns.push(args.next().ok_or("no required column")?);
+                },
+
+                "--reverse" => reversed.push(args.next().ok_or("no reversed question")?),
+
+                "--timeout" =>
+                {
+                    let seconds: f64 = args.next().ok_or("no timeout")?
+                        .parse().map_err(|error| format!("{error}"))?;
+
+                    let timeout = Duration::try_from_secs_f64(seconds)
+                        .map_err(|error| format!("{error}"))?;
+
+                    parse_options.timeout = Some(timeout);
+                },
+
+                "--fixed-width" =>
+                {
+                    let widths = args.next().ok_or("no column widths")?;
+                    parse_options.widths = Some(Self::parse_widths(&widths)?);
+                },
+
+                "--no-blank-lines" => parse_options.allow_blank_lines = false,
+                "--require-rectangular" => parse_options.require_rectangular = true,
+
                 "-r" | "--rank" => rank = true,
                 "-u" | "--unique" => unique = true,
                 "-e" | "--exact" => exact = true,
+                "--collapse-whitespace" => collapse_whitespace = true,
+                "--shell" => shell = true,
+                "--oneline" => oneline = true,
+                "--uid-first" => uid_first = true,
+                "--dedupe-uid" => dedupe_uid = true,
+                "--list-columns" => list_columns = true,
+                "--score-histogram" => score_histogram = true,
+                "--stream" => stream = true,
+                "--avg-selections" => avg_selections = true,
+                "--fail-on-empty" => fail_on_empty = true,
+                "--bayesian-rank" => bayesian_rank = true,
+                "--sanitize" => sanitize = true,
+                "--mapping-report" => mapping_report = true,
+                "--text-stats" => text_stats = true,
+                "--skip-stopwords" => skip_stopwords = true,
+                "--dedupe-answers" => dedupe_answers = true,
+                "--validate-numeric" => validate_numeric = true,
+                "--stamp" => stamp = true,
+                "--cumulative" => cumulative = true,
+                "--respondents-jsonl" => respondents_jsonl = true,
+                "--count-per-respondent" => count_per_respondent = true,
+                "--chi-square" => chi_square = true,
+                "--index-prefix" => index_prefix = true,
+                "--loose-numbers" => loose_numbers = true,
+                "--parse-stats" => parse_stats = true,
+                "--sort-by" => sort_by = Some(args.next().ok_or("no sort column")?),
+                "--nps" => nps = true,
+                "--boxplot" => boxplot = true,
+                "--explain-mapping-coverage" => mapping_coverage = true,
+                "--weighted-choices" => weighted_choices = true,
+                "--collate" => collate = Some(args.next().ok_or("no collate path")?),
                 _ => ()
             }
         }
 
-        let filepath = filepath.ok_or("no filepath specified")?;
+        // the schema doesnt depend on any input
+        let filepath = if json_schema
+        {
+            filepath.unwrap_or_default()
+        } else
+        {
+            filepath.ok_or("no filepath specified")?
+        };
+
+        // values given with -m win over the ones from the scale
+        for (point, value) in scale_values
+        {
+            mappings.entry(point).or_insert(value);
+        }
 
-        if !rank
+        if uid_first
         {
-            if search.is_empty()
+            if uid_column.is_some()
             {
-                return Err(String::from("no search string specified"));
+                return Err(String::from("--uid-first cant be combined with --uid-col"));
             }
+
+            uid_column = Some(0);
+        }
+
+        if chi_square && expected.len()<2
+        {
+            return Err(String::from("--chi-square needs at least two --expected answers"));
+        }
+
+        if cumulative && scale.is_empty()
+        {
+            return Err(String::from("--cumulative needs a --scale"));
+        }
+
+        if crosstab_out.is_some() && crosstab.is_none()
+        {
+            return Err(String::from("--crosstab-out needs --crosstab"));
+        }
+
+        if balance.is_some() && per_group.is_none()
+        {
+            return Err(String::from("--balance needs --per-group"));
+        }
+
+        if top_percent.is_some_and(|percent| !(percent>0.0 && percent<=100.0))
+        {
+            return Err(String::from("--top-percent must be above 0 and at most 100"));
+        }
+
+        if mapping_coverage && mappings.is_empty()
+        {
+            return Err(String::from("--explain-mapping-coverage needs mappings"));
+        }
+
+        if dedupe_uid && uid_column.is_none()
+        {
+            return Err(String::from("--dedupe-uid needs a uid column"));
         }
 
-        Ok(Config{filepath, search, rank, unique, exact, mappings})
+        let config = Config{filepath, search, rank, unique, exact, mappings, range, report, column,
+            parse_options, breakdown, collapse_whitespace, shell, required_columns,
+            state, oneline, uid_column, group_by, dedupe_uid,
+            list_columns, reversed, score_histogram, assert_average, stream, duration_column,
+            min_duration, min_average, avg_selections, fail_on_empty, stats, bayesian_rank, prior,
+            sanitize, compare_groups, mapping_report, trend, bucket, date_column,
+            text_stats, word_freq, skip_stopwords, dedupe_answers, bootstrap, seed,
+            balance, per_group, validate_numeric, stamp, merge_separator,
+            crosstab, crosstab_out, scale, cumulative, cohort, respondents_jsonl,
+            jaccard, columns_regex, count_per_respondent, expected, chi_square,
+            index_prefix, loose_numbers, parse_stats, sort_by, nps, max_categories,
+            weighted_choices, collate, top_percent, boxplot, mapping_coverage,
+            weight, json_schema, json};
+
+        // the mapping report is enough on its own
+        if !config.asks_question() && !config.mapping_report
+        {
+            return Err(String::from("no search string specified"));
+        }
+
+        Ok(config)
+    }
+
+    pub fn filepath(&self) -> &str
+    {
+        &self.filepath
+    }
+
+    pub fn search(&self) -> &str
+    {
+        &self.search
+    }
+
+    pub fn is_rank(&self) -> bool
+    {
+        self.rank
+    }
+
+    pub fn is_unique(&self) -> bool
+    {
+        self.unique
+    }
+
+    pub fn is_exact(&self) -> bool
+    {
+        self.exact
+    }
+
+    pub fn mappings(&self) -> &HashMap<String, i32>
+    {
+        &self.mappings
+    }
+
+    pub fn range(&self) -> Option<(usize, usize)>
+    {
+        self.range
+    }
+
+    pub fn column(&self) -> Option<isize>
+    {
+        self.column
+    }
+
+    pub fn uid_column(&self) -> Option<usize>
+    {
+        self.uid_column
+    }
+
+    pub fn parse_options(&self) -> &ParseOptions
+    {
+        &self.parse_options
+    }
+
+    pub fn stats(&self) -> &[String]
+    {
+        &self.stats
+    }
+
+    pub fn scale(&self) -> &[String]
+    {
+        &self.scale
+    }
+
+    pub fn seed(&self) -> Option<u64>
+    {
+        self.seed
+    }
+
+    fn selects_question(&self) -> bool
+    {
+        !self.search.is_empty() || self.column.is_some()
+            || (self.unique && self.uid_column.is_some())
+    }
+
+    // modes that run without a selected question
+    fn listing(&self) -> bool
+    {
+        self.oneline || self.list_columns || self.report.is_some() || self.assert_average.is_some()
+            || self.duration_column.is_some() || self.trend.is_some() || self.jaccard.is_some()
+            || self.collate.is_some() || self.mapping_coverage || self.json_schema || self.json
+    }
+
+    // anything to do besides the mapping report
+    fn asks_question(&self) -> bool
+    {
+        self.rank || self.listing() || self.selects_question()
     }
 
     fn parse_mappings(mapping: &str) -> Result<HashMap<String, i32>, String>
@@ -81,121 +605,3163 @@ impl Config
 
         Ok(mappings)
     }
-}
-
-#[cfg(test)]
-mod tests
-{
-    use super::*;
 
-    #[test]
-    fn mappings()
+    fn parse_average_assertion(text: &str) -> Result<(String, f64, f64), String>
     {
-        let mappings = Config::parse_mappings("test6tshouldt9twork!t3t!t4");
-        let mappings = mappings.expect("no mappings returned");
+        let (question, range) = text.rsplit_once(':')
+            .ok_or("average assertion should be <question>:<low>-<high>")?;
 
-        assert_eq!(mappings.get("es"), Some(&6));
-        assert_eq!(mappings.get("should"), Some(&9));
-        assert_eq!(mappings.get("work!"), Some(&3));
-        assert_eq!(mappings.get("!"), Some(&4));
-    }
-}
+        let split = range.char_indices().skip(1)
+            .find(|(_, c)| *c=='-')
+            .map(|(index, _)| index)
+            .ok_or("average range should be <low>-<high>")?;
 
-mod csv;
-use csv::csv_reader::Answers;
+        let low: f64 = range[..split].parse().map_err(|error| format!("{error}"))?;
+        let high: f64 = range[split+1..].parse().map_err(|error| format!("{error}"))?;
 
-pub fn run(config: &Config) -> Result<(), Box<dyn Error>>
-{
-    let file = fs::read_to_string(&config.filepath)?;
+        if low>high
+        {
+            return Err(format!("average range low ({low}) is above its high ({high})"));
+        }
 
-    let answers = Answers::parse(&file)?;
+        Ok((question.to_string(), low, high))
+    }
 
-    if !config.rank
+    #[allow(clippy::suspicious_else_formatting)]
+    fn parse_stats(names: &str) -> Result<Vec<String>, String>
     {
-        let replies =
+        names.split(',').map(|name|
         {
-            if config.exact
+            let name = name.trim();
+            if STATS.contains(&name)
             {
-                answers.question_exact(&config.search)
+                Ok(name.to_string())
             } else
             {
-                answers.question(&config.search)
+                Err(format!("unknown stat {name}, expected one of {}", STATS.join(", ")))
             }
-        }.ok_or(format!("cant find {}", &config.search))?;
+        }).collect()
+    }
 
-        if config.unique
+    // either plain answers in order or every answer with its value (<answer>=<value>)
+    fn parse_scale(text: &str) -> Result<(Vec<String>, HashMap<String, i32>), String>
+    {
+        let points: Vec<&str> = text.split(',').map(|point| point.trim()).collect();
+
+        if !text.contains('=')
         {
-            print_unique(config, &answers, replies)
-        } else
+            return Ok((points.into_iter().map(String::from).collect(), HashMap::new()));
+        }
+
+        let mut scale = Vec::new();
+        let mut values = HashMap::new();
+        for point in points
         {
-            print_normal(config, replies)
+            let (answer, value) = point.rsplit_once('=')
+                .ok_or(format!("scale point {point} has no value, expected <answer>=<value>"))?;
+
+            let answer = answer.trim().to_string();
+            let value = value.trim().parse().map_err(|error| format!("{error}"))?;
+
+            scale.push(answer.clone());
+            values.insert(answer, value);
         }
-    } else
-    {
-        print_ranked(config, answers)
+
+        Ok((scale, values))
     }
-}
 
-fn print_normal(config: &Config, replies: Vec<&str>) -> Result<(), Box<dyn Error>>
-{
-    println!("{}", replies[0]);
+    fn parse_expected(text: &str) -> Result<Vec<(String, f64)>, String>
+    {
+        text.split(',').map(|pair|
+        {
+            let (answer, proportion) = pair.rsplit_once('=')
+                .ok_or("expected proportions should be <answer>=<proportion>,...")?;
 
-    let no_label_replies = replies.into_iter().skip(1);
-    let mode = mode(no_label_replies.clone()).ok_or("no replies given")?;
-    println!("most popular: {mode}");
+            let proportion: f64 = proportion.trim().parse().map_err(|error| format!("{error}"))?;
+            if proportion<=0.0
+            {
+                return Err(format!("expected proportion of {answer} should be above zero"));
+            }
 
-    if !config.mappings.is_empty()
+            Ok((answer.trim().to_string(), proportion))
+        }).collect()
+    }
+
+    #[allow(clippy::suspicious_else_formatting)]
+    fn parse_bucket(name: &str) -> Result<String, String>
+    {
+        if BUCKETS.contains(&name)
+        {
+            Ok(name.to_string())
+        } else
+        {
+            Err(format!("unknown date bucket {name}, expected one of {}", BUCKETS.join(", ")))
+        }
+    }
+
+    fn parse_comparison(text: &str) -> Result<(String, String, String), String>
     {
-        let mapped: Vec<i32> = map_replies(no_label_replies.clone(), &config.mappings);
+        let error = "group comparison should be <question>:<group>,<group>";
 
-        let median = median(&mapped);
-        let average = average(&mapped);
-        println!("average: {average:.2}, median: {median:.2}");
+        let (column, groups) = text.rsplit_once(':').ok_or(error)?;
+        let (first, second) = groups.split_once(',').ok_or(error)?;
+
+        Ok((column.to_string(), first.to_string(), second.to_string()))
     }
 
-    let display_replies = no_label_replies.clone().filter(|text| !text.is_empty());
-    if config.mappings.is_empty()
+    fn parse_widths(widths: &str) -> Result<Vec<usize>, String>
     {
-        println!("all replies: {}", format_replies(display_replies));
-    } else
+        widths.split(',')
+            .map(|width| width.trim().parse().map_err(|error| format!("{error}")))
+            .collect()
+    }
+
+    fn parse_range(text: &str) -> Result<(usize, usize), String>
     {
-        let sorted_replies = sort_replies(
-            display_replies.collect::<Vec<&str>>(),
-            &config.mappings);
+        let (start, end) = text.split_once(':').ok_or("range should be <start>:<end>")?;
 
-        println!("sorted replies: {}", format_replies(sorted_replies.into_iter()));
+        let start: usize = start.parse().map_err(|error| format!("{error}"))?;
+        let end: usize = end.parse().map_err(|error| format!("{error}"))?;
+
+        if start>end
+        {
+            return Err(format!("range start ({start}) is after its end ({end})"));
+        }
+
+        Ok((start, end))
     }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn accessors()
+    {
+        let args = ["-s", "favorite color", "-r", "-e", "-m", ",red,1,blue,2", "--range", "2:5",
+            "--col", "-1", "--stats", "iqr", "--scale", "low,high", "--seed", "9",
+            "--fixed-width", "4,6", "answers.csv"];
+
+        let config = Config::build(args.into_iter().map(String::from)).unwrap();
+
+        assert_eq!(config.filepath(), "answers.csv");
+        assert_eq!(config.search(), "favorite color");
+        assert!(config.is_rank());
+        assert!(config.is_exact());
+        assert!(!config.is_unique());
+        assert_eq!(config.mappings(),
+            &HashMap::from([(String::from("red"), 1), (String::from("blue"), 2)]));
+        assert_eq!(config.range(), Some((2, 5)));
+        assert_eq!(config.column(), Some(-1));
+        assert_eq!(config.uid_column(), None);
+        assert_eq!(config.parse_options().widths, Some(vec![4, 6]));
+        assert_eq!(config.stats(), [String::from("iqr")]);
+        assert_eq!(config.scale(), [String::from("low"), String::from("high")]);
+        assert_eq!(config.seed(), Some(9));
+    }
+
+    #[test]
+    fn mappings()
+    {
+        let mappings = Config::parse_mappings("test6tshouldt9twork!t3t!t4");
+        let mappings = mappings.expect("no mappings returned");
+
+        assert_eq!(mappings.get("es"), Some(&6));
+        assert_eq!(mappings.get("should"), Some(&9));
+        assert_eq!(mappings.get("work!"), Some(&3));
+        assert_eq!(mappings.get("!"), Some(&4));
+    }
+
+    #[test]
+    fn ranges()
+    {
+        assert_eq!(Config::parse_range("1000:2000"), Ok((1000, 2000)));
+        assert_eq!(Config::parse_range("5:5"), Ok((5, 5)));
+
+        assert!(Config::parse_range("2000:1000").is_err());
+        assert!(Config::parse_range("10").is_err());
+    }
+
+    #[test]
+    fn widths()
+    {
+        assert_eq!(Config::parse_widths("10,20, 15"), Ok(vec![10, 20, 15]));
+        assert!(Config::parse_widths("10,,15").is_err());
+    }
+
+    #[test]
+    fn average_assertions()
+    {
+        assert_eq!(Config::parse_average_assertion("Question:3.0-4.0"),
+            Ok((String::from("Question"), 3.0, 4.0)));
+
+        assert_eq!(Config::parse_average_assertion("a:b:-2-1.5"),
+            Ok((String::from("a:b"), -2.0, 1.5)));
+
+        assert!(Config::parse_average_assertion("Question:4-3").is_err());
+        assert!(Config::parse_average_assertion("Question").is_err());
+
+        let args = ["-m", ",yes,5,no,1", "--assert-average", "q:3.0-4.0", "file"];
+        let config = Config::build(args.into_iter().map(String::from)).unwrap();
+
+        let answers = Answers::parse("\"q1\", \"q2\"\n\"yes\", \"no\"\n\"no\", \"no\"").unwrap();
+
+        assert_eq!(check_average(&config, &answers, "q1", 3.0, 4.0),
+            Ok(String::from("average of q1 is 3.00, within 3-4")));
+
+        assert_eq!(check_average(&config, &answers, "q2", 3.0, 4.0),
+            Err(String::from("average of q2 is 1.00, outside 3-4")));
+    }
+
+    #[test]
+    fn duration()
+    {
+        let answers = Answers::parse(
+            "\"q1\", \"seconds\"
+            \"a\", \"100\"
+            \"b\", \"40\"
+            \"c\", \"\"
+            \"d\", \"120.5\"
+            \"e\", \"80\"
+            \"f\", \"10\"").unwrap();
+
+        let durations = durations(&answers, 1, false);
+        assert_eq!(durations, vec![100.0, 40.0, 120.5, 80.0, 10.0]);
+
+        assert_eq!(duration_stats(&durations, None), Some(DurationStats{
+            average: 70.1,
+            median: 80.0,
+            threshold: 40.0,
+            fast: 1
+        }));
+
+        assert_eq!(duration_stats(&durations, Some(50.0)).unwrap().fast, 2);
+        assert_eq!(duration_stats(&[], None), None);
+    }
+
+    #[test]
+    fn bootstrap_interval()
+    {
+        let values = [1, 2, 2, 3, 3, 3, 4, 4, 5, 5];
+
+        let interval = |seed| bootstrap(&values, 1000, &mut Rng::new(seed)).unwrap();
+
+        let (low, high) = interval(7);
+        assert_eq!((low, high), (2.4, 3.9));
+        assert_eq!(interval(7), (low, high));
+
+        let mean = average(&values);
+        assert!(low<mean && mean<high);
+
+        assert_eq!(bootstrap(&[3, 3, 3], 100, &mut Rng::new(1)), Some((3.0, 3.0)));
+        assert_eq!(bootstrap(&[], 100, &mut Rng::new(1)), None);
+
+        let mut rng = Rng::new(42);
+        assert!((0..100).all(|_| rng.below(10)<10));
+    }
+
+    #[test]
+    fn cohort()
+    {
+        let file = "\"time\", \"platform\", \"rating\"
+            \"1\", \"mobile\", \"good\"
+            \"2\", \"desktop\", \"bad\"
+            \"3\", mobile;desktop, \"good\"
+            \"4\", \"mobile\", \"okay\"
+            \"5\", \"tablet\", \"bad\"
+            \"6\", \"\", \"bad\"";
+
+        let config = Config::build(["-s", "rating", "--cohort", "platform", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        let (answers, _) = prepare(&config, file, Instant::now()).unwrap();
+        assert_eq!(question(&config, &answers).unwrap(), vec!["rating", "good", "good", "okay"]);
+
+        let report = AnalysisReport::new(&config, &answers).unwrap();
+        assert_eq!(report.questions[0].most_popular, Some(String::from("good")));
+
+        let blank = Answers::parse("\"q\"\n\"\"").unwrap();
+        assert!(cohort_indices(&blank, 0).is_empty());
+    }
+
+    #[test]
+    fn balanced_groups()
+    {
+        let mut answers = Answers::parse(
+            "\"time\", \"class\", \"mood\"
+            \"1\", \"a\", \"good\"
+            \"2\", \"a\", \"bad\"
+            \"3\", \"b\", \"good\"
+            \"4\", \"a\", \"good\"
+            \"5\", \"\", \"bad\"
+            \"6\", \"b\", \"bad\"
+            \"7\", \"c\", \"good\"
+            \"8\", \"a\", \"bad\"
+            \"9\", \"b\", \"good\"").unwrap();
+
+        let indices = balanced_indices(&answers, 1, 2, &mut Rng::new(3));
+        assert_eq!(indices, balanced_indices(&answers, 1, 2, &mut Rng::new(3)));
+
+        answers.retain_indices(&indices);
+
+        let sizes: Vec<(&str, usize)> = answers.group_by(1, 2).into_iter()
+            .map(|(group, replies)| (group, replies.len()))
+            .collect();
+
+        assert_eq!(sizes, vec![("a", 2), ("b", 2), ("c", 1)]);
+
+        assert!(Config::build(["--balance", "class", "-s", "mood", "file"]
+            .into_iter().map(String::from)).is_err());
+    }
+
+    #[test]
+    fn coefficient_of_variation()
+    {
+        let values = [2, 4, 4, 4, 5, 5, 7, 9];
+
+        assert_eq!(std_dev(&values), (32.0f64/7.0).sqrt());
+        assert_eq!(cv(&values), (32.0f64/7.0).sqrt()/5.0);
+
+        assert_eq!(cv(&[-1, 1, 0]), 0.0);
+        assert_eq!(cv(&[]), 0.0);
+        assert_eq!(std_dev(&[3]), 0.0);
+
+        assert_eq!(Config::parse_stats("cv,std_dev"),
+            Ok(vec![String::from("cv"), String::from("std_dev")]));
+    }
+
+    #[test]
+    fn interquartile_range()
+    {
+        assert_eq!(percentile(&[5, 1, 3], 50.0), 3.0);
+        assert_eq!(percentile(&[1, 2, 3, 4], 25.0), 1.75);
+
+        assert_eq!(iqr(&[8, 1, 7, 2, 6, 3, 5, 4]), 3.5);
+        assert_eq!(iqr(&[1, 1, 1, 10]), 2.25);
+
+        assert_eq!(iqr(&[]), 0.0);
+        assert_eq!(iqr(&[4]), 0.0);
+
+        assert_eq!(Config::parse_stats("average, iqr"),
+            Ok(vec![String::from("average"), String::from("iqr")]));
+
+        assert!(Config::parse_stats("average,mode").is_err());
+    }
+
+    #[test]
+    fn jaccard_similarity()
+    {
+        let answers = Answers::parse(
+            "\"uid\", \"likes\", \"uses\"
+            \"a\", cat;dog, cat;dog
+            \"b\", cat;dog, dog;fish
+            \"c\", \"\", \"\"
+            \"d\", \"cat\", \"\"
+            \"e\", cat;dog;cat, fish;dog;bird").unwrap();
+
+        let (similarity, amount) = jaccard(answers.cells(1).zip(answers.cells(2))).unwrap();
+        assert_eq!(amount, 4);
+        assert_eq!(similarity, (1.0+1.0/3.0+0.0+1.0/4.0)/4.0);
+
+        let blank = Answers::parse("\"q1\", \"q2\"\n\"\", \"\"").unwrap();
+        assert_eq!(jaccard(blank.cells(0).zip(blank.cells(1))), None);
+
+        let config = Config::build(["--jaccard", "likes,uses", "file"].into_iter().map(String::from))
+            .unwrap();
+
+        assert_eq!(config.jaccard, Some((String::from("likes"), String::from("uses"))));
+    }
+
+    #[test]
+    fn monthly_trend()
+    {
+        let answers = Answers::parse(
+            "\"time\", \"mood\", \"submitted\"
+            \"2024-03-02 10:00:00\", \"good\", \"12/30/2023\"
+            \"2024/03/20\", \"bad\", \"1/2/2024\"
+            \"3/31/2024 11:59:00 PM\", \"good\", \"1/5/2024\"
+            \"2024-04-10\", \"bad\", \"1/9/2024\"
+            \"2024-05-01\", \"\", \"2/1/2024\"").unwrap();
+
+        let build = |args: &str| Config::build(args.split(' ').map(String::from)).unwrap();
+
+        let config = build("--trend mood -m ,good,1,bad,0 file");
+        assert_eq!(trend(&config, &answers, 1).unwrap(), vec![
+            (String::from("2024-03"), 2.0/3.0),
+            (String::from("2024-04"), 0.0)
+            ]);
+
+        let config = build("--trend mood --by year --date-col submitted -m ,good,1,bad,0 file");
+        assert_eq!(trend(&config, &answers, 1).unwrap(), vec![
+            (String::from("2023"), 1.0),
+            (String::from("2024"), 1.0/3.0)
+            ]);
+
+        assert_eq!(parse_date("2024-02-29"), Some((2024, 2, 29)));
+        assert_eq!(parse_date("2024-02-30"), None);
+        assert_eq!(parse_date("2/29/2023"), None);
+        assert_eq!(parse_date("2000-02-29"), Some((2000, 2, 29)));
+        assert_eq!(parse_date("1900-02-29"), None);
+        assert_eq!(parse_date("2024-04-31"), None);
+        assert_eq!(parse_date("13/01/2024"), None);
+        assert_eq!(parse_date("yesterday"), None);
+
+        assert!(trend(&build("--trend mood file"), &answers, 1).is_err());
+        assert!(Config::build(["--trend", "mood", "--by", "week", "file"]
+            .into_iter().map(String::from)).is_err());
+    }
+
+    #[test]
+    fn loose_numbers()
+    {
+        assert_eq!(parse_loose_number("$1,234.50"), Some(1234.5));
+        assert_eq!(parse_loose_number("1 234,50"), Some(1234.5));
+        assert_eq!(parse_loose_number("42"), Some(42.0));
+        assert_eq!(parse_loose_number(" €1.234.567,8 "), Some(1234567.8));
+        assert_eq!(parse_loose_number("1,234"), Some(1234.0));
+        assert_eq!(parse_loose_number("-3,5"), Some(-3.5));
+        assert_eq!(parse_loose_number("-$5"), Some(-5.0));
+        assert_eq!(parse_loose_number("1 234 567"), Some(1234567.0));
+        assert_eq!(parse_loose_number("n/a"), None);
+
+        assert_eq!(parse_loose_number("2 to 3"), None);
+        assert_eq!(parse_loose_number("5 out of 10"), None);
+        assert_eq!(parse_loose_number("2-3"), None);
+        assert_eq!(parse_loose_number("2 3"), None);
+        assert_eq!(parse_loose_number("12 3456"), None);
+        assert_eq!(parse_loose_number("-3,5 kg"), None);
+        assert_eq!(parse_loose_number("$"), None);
+
+        assert_eq!(parse_number("$5", false), None);
+        assert_eq!(parse_number("$5", true), Some(5.0));
+    }
+
+    #[test]
+    fn sanitized()
+    {
+        assert_eq!(sanitize("red\u{1b}[31m\tbold\u{7f}"), "red^[[31m^Ibold^?");
+        assert_eq!(sanitize("line\nbreak\u{9b}"), "line^Jbreak\\u{9b}");
+        assert_eq!(sanitize("fine 🥺"), "fine 🥺");
+
+        let mut answers = Answers::parse("\"q\u{7}1\"\n\"a\u{1b}b\"").unwrap();
+        answers.map_all(sanitize);
+
+        assert_eq!(answers.question("q"), Some(vec!["q^G1", "a^[b"]));
+    }
+
+    #[test]
+    fn compare_groups()
+    {
+        assert_eq!(Config::parse_comparison("plan:A,B"),
+            Ok((String::from("plan"), String::from("A"), String::from("B"))));
+
+        assert!(Config::parse_comparison("plan:A").is_err());
+
+        let comparison = compare_means(&[4, 5, 6], &[1, 3]);
+        assert_eq!(comparison.means, (5.0, 2.0));
+        assert_eq!(comparison.difference, 3.0);
+        assert_eq!(comparison.t, Some(3.0/(1.0f64/3.0+2.0/2.0).sqrt()));
+
+        assert_eq!(compare_means(&[4], &[1, 3]).t, None);
+        assert_eq!(compare_means(&[2, 2], &[2, 2]).t, None);
+    }
+
+    #[test]
+    fn breakdowns()
+    {
+        let replies = ["yes", "no", "", "yes", "maybe", "no"];
+        let (matched, other) = breakdown(replies.into_iter(), "no");
+
+        assert_eq!((matched, other), (2, 3));
+        assert_eq!(format_breakdown(matched, other), "matched 2 (40%), other 3 (60%)");
+
+        assert_eq!(format_breakdown(0, 0), "matched 0 (0%), other 0 (0%)");
+    }
+
+    #[test]
+    fn shell_format()
+    {
+        assert_eq!(shell_variable("average", "3.14"), "QUIZ_AVERAGE=3.14");
+        assert_eq!(shell_variable("most popular", "it's ok"), "QUIZ_MOST_POPULAR='it'\\''s ok'");
+        assert_eq!(shell_variable("mode", ""), "QUIZ_MODE=''");
+
+        let args = ["-s", "q", "-m", ",yes,1,no,0", "--shell", "file"];
+        let config = Config::build(args.into_iter().map(String::from)).unwrap();
+
+        let output = shell_output(&config, &["how? (pick one)", "yes", "no", "yes", ""]);
+        assert_eq!(output.lines().collect::<Vec<&str>>(), vec![
+            "QUIZ_QUESTION='how? (pick one)'",
+            "QUIZ_RESPONSES=3",
+            "QUIZ_MOST_POPULAR=yes",
+            "QUIZ_AVERAGE=0.67",
+            "QUIZ_MEDIAN=1.00"
+            ]);
+    }
+
+    #[test]
+    fn list_columns()
+    {
+        let answers = Answers::parse("\"time\", \"q1\", \"q2\"\n\"1\", \"a\", \"b\"").unwrap();
+
+        assert_eq!(column_lines(&answers), vec!["0: time", "1: q1", "2: q2"]);
+    }
+
+    #[test]
+    fn required_columns()
+    {
+        let answers = Answers::parse("\"uid\", \"score\"\n\"a\", \"1\"").unwrap();
+
+        let required = vec![String::from("uid"), String::from("score")];
+        assert_eq!(check_required(&answers, &required), Ok(()));
+
+        let required = vec![String::from("uid"), String::from("time"), String::from("sco")];
+        assert_eq!(check_required(&answers, &required),
+            Err(String::from("missing required columns: time, sco")));
+    }
+
+    #[test]
+    fn columns_regex()
+    {
+        let answers = Answers::parse(
+            "\"time\", \"Q1 mood\", \"Q12 sleep\", \"notes Q\"
+            \"1\", \"good\", \"8\", \"none\"").unwrap();
+
+        let build = |args: &[&str]| Config::build(args.iter().map(|arg| arg.to_string())).unwrap();
+
+        let labels = |lines: Vec<String>| -> Vec<String>
+        {
+            lines.into_iter().map(|line| line.split('\t').next().unwrap().to_string()).collect()
+        };
+
+        let config = build(&["--oneline", "--columns-regex", r"^Q\d+", "file"]);
+        assert_eq!(labels(oneline(&config, &answers)), vec!["Q1 mood", "Q12 sleep"]);
+
+        let report = report(&config, &answers);
+        assert!(report.contains("| Q12 sleep |") && !report.contains("notes Q"));
+
+        let config = build(&["--oneline", "file"]);
+        assert_eq!(oneline(&config, &answers).len(), 4);
+
+        assert!(Config::build(["--oneline", "--columns-regex", "(", "file"]
+            .into_iter().map(String::from)).is_err());
+    }
+
+    #[test]
+    fn index_prefix()
+    {
+        let answers = Answers::parse(
+            "\"time\", \"q1\", \"Question\"
+            \"1\", \"a\", \"yes\"").unwrap();
+
+        let build = |args: &[&str]| Config::build(args.iter().map(|arg| arg.to_string())).unwrap();
+
+        let config = build(&["--oneline", "--index-prefix", "file"]);
+        assert_eq!(heading(&config, answers.labels(), "Question"), "[2] Question");
+        assert_eq!(oneline(&config, &answers)[2], "[2] Question\t1\tyes\t");
+        assert!(report(&config, &answers).contains("### [1] q1"));
+
+        let crosstab = CrossTab::new(&answers, 1, 2)
+            .with_label(&column_heading(&config, 1, &answers.labels()[1]));
+        assert_eq!(crosstab.lines()[0], "[1] q1\tyes");
+
+        let config = build(&["-r", "-m", ",a,1,yes,2", "--index-prefix", "--state", "st.tsv", "file"]);
+        let aggregate = Aggregate::from_answers(&answers, |label| column_mappings(&config, label));
+        assert_eq!(ranked_lines(&config, answers.labels(), aggregate.ranked()),
+            vec!["[2] Question: average 2.00", "[1] q1: average 1.00"]);
+
+        let config = build(&["--oneline", "file"]);
+        assert_eq!(heading(&config, answers.labels(), "Question"), "Question");
+        assert_eq!(column_heading(&config, 1, "q1"), "q1");
+    }
+
+    #[test]
+    fn oneline_fields()
+    {
+        let answers = Answers::parse(
+            "\"q1\", \"q\t2\", \"q3\"\n\"yes\", \"no\", \"\"\n\"yes\", \"a\tb\", \"\"").unwrap();
+
+        for mappings in ["", " -m ,yes,1,no,0"]
+        {
+            let args = format!("--oneline{mappings} file");
+            let config = Config::build(args.split(' ').map(String::from)).unwrap();
+
+            let lines = oneline(&config, &answers);
+            assert_eq!(lines.len(), 3);
+
+            for line in &lines
+            {
+                assert_eq!(line.split('\t').count(), 4, "{line}");
+            }
+        }
+
+        let config = Config::build(["--oneline", "file"].into_iter().map(String::from)).unwrap();
+        assert_eq!(oneline(&config, &answers)[0], "q1\t2\tyes\t");
+    }
+
+    #[test]
+    fn uid_first()
+    {
+        let build = |args: &str| Config::build(args.split(' ').map(String::from));
+
+        let config = build("-u --uid-first file").unwrap();
+        assert_eq!(config.uid_column, Some(0));
+
+        let answers = Answers::parse("\"uid\", \"q1\"\n\"tom\", \"yes\"\n\"ann\", \"no\"")
+            .unwrap();
+
+        assert_eq!(question(&config, &answers), Ok(vec!["uid", "tom", "ann"]));
+
+        assert!(build("-u --uid-first --uid-col 1 file").is_err());
+        assert!(build("-u file").is_err());
+
+        assert!(build("-s q1 --dedupe-uid file").is_err());
+        assert!(build("-s q1 --uid-first --dedupe-uid file").is_ok());
+    }
+
+    #[test]
+    fn group_modes()
+    {
+        let answers = Answers::parse(
+            "\"team\", \"pick\"
+            \"red\", \"cats\"
+            \"blue\", \"dogs\"
+            \"red\", cats;dogs
+            \"blue\", \"dogs\"
+            \"red\", \"cats\"
+            \"\", \"dogs\"").unwrap();
+
+        let config = Config::build(["-s", "pick", "--group-by", "team", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        let groups = answers.group_by(0, 1);
+        assert_eq!(group_lines(&config, "pick", groups), vec![
+            "blue: most popular: dogs",
+            "red: most popular: cats"
+            ]);
+
+        let config = Config::build(["-s", "pick", "-m", ",cats,1,dogs,0", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        let groups = answers.group_by(0, 1);
+        assert_eq!(group_lines(&config, "pick", groups), vec![
+            "blue: most popular: dogs, average: 0.00",
+            "red: most popular: cats, average: 0.75"
+            ]);
+
+        let config = Config::build(["-s", "pick", "-m", ",cats,1,dogs,0", "--reverse", "pick", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        let groups = answers.group_by(0, 1);
+        assert_eq!(group_lines(&config, "pick", groups), vec![
+            "blue: most popular: dogs, average: 1.00",
+            "red: most popular: cats, average: 0.25"
+            ]);
+    }
+
+    #[test]
+    fn valued_scale()
+    {
+        let build = |args: &[&str]| Config::build(args.iter().map(|arg| arg.to_string()));
+
+        let config = build(&["-s", "q", "--scale", "never=0, rarely=1,sometimes=3", "file"]).unwrap();
+        assert_eq!(config.scale, vec!["never", "rarely", "sometimes"]);
+
+        let mapped = map_replies(["rarely", "sometimes", "never", "sometimes"].into_iter(),
+            &config.mappings);
+
+        assert_eq!(average(&mapped), 7.0/4.0);
+
+        let config = build(&["-s", "q", "-m", ",never,5", "--scale", "never=0,rarely=1", "file"]).unwrap();
+        assert_eq!(config.mappings.get("never"), Some(&5));
+        assert_eq!(config.mappings.get("rarely"), Some(&1));
+
+        let config = build(&["-s", "q", "--scale", "never,rarely", "file"]).unwrap();
+        assert!(config.mappings.is_empty());
+        assert_eq!(scale_mappings(&config.scale).get("rarely"), Some(&2));
+
+        assert!(build(&["-s", "q", "--scale", "never=0,rarely", "file"]).is_err());
+        assert!(build(&["-s", "q", "--scale", "never=zero", "file"]).is_err());
+    }
+
+    #[test]
+    fn median_answer()
+    {
+        let build = |args: &[&str]| Config::build(args.iter().map(|arg| arg.to_string())).unwrap();
+
+        let replies = ["often", "never", "sometimes", "sometimes", "always", "often"];
+
+        let config = build(&["-s", "q", "--scale", "never,sometimes,often,always", "file"]);
+        let ordinal = scale_mappings(&config.scale);
+        let middle = median(&map_replies(replies.into_iter(), &ordinal));
+
+        assert_eq!(middle, 2.5);
+        assert_eq!(median_label(&ordinal, middle), Some(String::from("sometimes")));
+        assert_eq!(median_label(&ordinal, 2.6), Some(String::from("often")));
+
+        let config = build(&["-s", "q", "-m", ",no,0,nah,0,yes,5", "file"]);
+        assert_eq!(median_label(&config.mappings, 1.0), Some(String::from("nah/no")));
+        assert_eq!(median_label(&config.mappings, 4.0), Some(String::from("yes")));
+
+        assert_eq!(median_label(&HashMap::new(), 1.0), None);
+    }
+
+    #[test]
+    fn mapping_report()
+    {
+        let config = Config::build(["--mapping-report", "-m", ",often,3,never,1,sometimes,2,always,3",
+            "-s", "q1", "file"].into_iter().map(String::from)).unwrap();
+
+        assert_eq!(mapping_lines(&config.mappings), vec![
+            "never = 1",
+            "sometimes = 2",
+            "always = 3",
+            "often = 3"
+            ]);
+
+        assert!(mapping_lines(&HashMap::new()).is_empty());
+
+        let config = Config::build(["--mapping-report", "-m", ",yes,1,no,0", "missing.csv"]
+            .into_iter().map(String::from)).unwrap();
+
+        assert!(!config.asks_question());
+        assert!(run(&config).is_ok());
+    }
+
+    #[test]
+    fn json_schema_args()
+    {
+        let build = |args: &[&str]| Config::build(args.iter().map(|arg| arg.to_string()));
+
+        assert!(build(&["--json-schema"]).unwrap().json_schema);
+        assert_eq!(build(&["--json-schema", "file"]).unwrap().filepath(), "file");
+        assert!(build(&["--json", "file"]).unwrap().json);
+        assert!(build(&["-s", "q1"]).is_err());
+    }
+
+    #[test]
+    fn mapping_coverage_report()
+    {
+        let config = Config::build(["--explain-mapping-coverage", "-m", ",yes,1,no,0,maybe,2", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        let answers = Answers::parse(
+            "\"time\", \"q1\", \"q2\", \"q3\", \"q4\"
+            \"1\", \"yes\", \"yes\", \"sure\", \"\"
+            \"2\", \"no\", \"nah\", \"yes\", \"\"
+            \"3\", \"maybe\", \"no\", \"\", \" \"
+            \"4\", \"\", \"dunno\", \"nope\", \"\"").unwrap();
+
+        let coverage = mapping_coverage(&config, &answers);
+        assert_eq!(coverage, vec![("time", 0.0), ("q3", 1.0/3.0*100.0), ("q2", 50.0), ("q1", 100.0)]);
+
+        let build = |args: &[&str]| Config::build(args.iter().map(|arg| arg.to_string()));
+        assert!(build(&["--explain-mapping-coverage", "file"]).is_err());
+    }
+
+    #[test]
+    fn reverse_coded()
+    {
+        let args = ["-r", "-m", ",never,1,sometimes,2,often,5", "--reverse", "calm", "file"];
+        let config = Config::build(args.into_iter().map(String::from)).unwrap();
+
+        let reversed = column_mappings(&config, "i feel calm");
+        assert_eq!(reversed.get("never"), Some(&5));
+        assert_eq!(reversed.get("sometimes"), Some(&4));
+        assert_eq!(reversed.get("often"), Some(&1));
+
+        let answers = Answers::parse(
+            "\"time\", \"i feel anxious\", \"i feel calm\"
+            \"1\", \"often\", \"never\"
+            \"2\", \"sometimes\", \"often\"").unwrap();
+
+        assert_eq!(ranked(&config, &answers), vec![("i feel anxious", Some(3.5)), ("i feel calm", Some(3.0))]);
+
+        let calm = answers.question("calm").unwrap();
+        let mapped = map_replies(calm.iter().skip(1).copied(), &column_mappings(&config, calm[0]));
+        assert_eq!(average(&mapped), 3.0);
+
+        let reply = answers.reply(0).unwrap();
+        assert_eq!(respondent_mapped(&config, answers.labels(), reply), vec![5, 5]);
+    }
+
+    #[test]
+    fn reverse_coded_state()
+    {
+        let path = std::env::temp_dir().join("quizanalyze_reverse_state.tsv");
+        let _ = fs::remove_file(&path);
+
+        let args = ["-r", "-m", ",never,1,sometimes,2,often,5", "--reverse", "calm",
+            "--state", path.to_str().unwrap(), "file"];
+        let config = Config::build(args.into_iter().map(String::from)).unwrap();
+
+        let answers = Answers::parse(
+            "\"time\", \"i feel anxious\", \"i feel calm\"
+            \"1\", \"often\", \"never\"
+            \"2\", \"sometimes\", \"often\"").unwrap();
+
+        let aggregate = Aggregate::from_answers(&answers, |label| column_mappings(&config, label));
+        assert_eq!(aggregate.ranked(), ranked(&config, &answers));
+
+        print_ranked_state(&config, &answers, path.to_str().unwrap()).unwrap();
+        print_ranked_state(&config, &answers, path.to_str().unwrap()).unwrap();
+
+        let saved = Aggregate::deserialize(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.ranked(), vec![("i feel anxious", Some(3.5)), ("i feel calm", Some(3.0))]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn weighted_plurality()
+    {
+        let answers = Answers::parse(
+            "\"weight\", \"pick\"
+            \"1\", \"tea\"
+            \"1\", \"tea\"
+            \"0.5\", \"tea\"
+            \"3\", coffee;cake
+            \"\", \"coffee\"
+            \"2\", \"\"").unwrap();
+
+        let replies = answers.question("pick").unwrap();
+        assert_eq!(mode(replies.iter().skip(1).copied(), None), Some("tea"));
+
+        let weights = choice_weights(&answers, 1, 0).unwrap();
+        assert_eq!(weights, vec![1.0, 1.0, 0.5, 3.0, 3.0, 1.0, 2.0]);
+        assert_eq!(mode(replies.iter().skip(1).copied(), Some(&weights)), Some("coffee"));
+
+        let even = vec![1.0; weights.len()];
+        assert_eq!(mode(replies.iter().skip(1).copied(), Some(&even)), Some("tea"));
+        assert_eq!(mode(["", " "].into_iter(), Some(&even)), None);
+        assert_eq!(mode(["tea", "coffee", "coffee"].into_iter(), Some(&[3.0])), Some("tea"));
+
+        for weight in ["-1", "inf", "NaN"]
+        {
+            let file = format!("\"weight\", \"pick\"\n\"1\", \"tea\"\n\"{weight}\", \"tea\"");
+            let answers = Answers::parse(&file).unwrap();
+
+            let error = choice_weights(&answers, 1, 0).unwrap_err();
+            assert!(matches!(error, Error::Parse(_)));
+            assert_eq!(error.to_string(), format!("parse error: row 2 has an invalid weight {weight}"));
+        }
+    }
+
+    #[test]
+    fn whitespace_answers()
+    {
+        let replies = ["", "   ", "\t", "   ", "yes", "   ", "no", "yes"];
+
+        assert_eq!(mode(replies.into_iter(), None), Some("yes"));
+        assert_eq!(distribution(replies.into_iter()), vec![("yes", 2), ("no", 1)]);
+        assert_eq!(breakdown(replies.into_iter(), "yes"), (2, 1));
+
+        let answers = Answers::parse("\"q1\"\n\"  \"\n\"  \"\n\"a\"").unwrap();
+        let stats = QuestionStats::new(&Config::build(["-s", "q1", "file"]
+            .into_iter().map(String::from)).unwrap(), &answers.question("q1").unwrap());
+
+        assert_eq!(stats.responses, 1);
+        assert_eq!(stats.most_popular, Some(String::from("a")));
+    }
+
+    #[test]
+    fn merged_display()
+    {
+        let answers = Answers::parse(
+            "\"uid\", \"pets\"
+            \"a\", cat;dog;fish
+            \"b\", \"\"
+            \"c\", dog
+            \"d\", cat;dog").unwrap();
+
+        let merged = merged_replies(answers.cells(1), "+");
+        assert_eq!(merged, vec!["cat+dog+fish", "dog", "cat+dog"]);
+        assert_eq!(format_replies(merged.iter().map(|reply| &reply[..])), "cat+dog+fish, dog, cat+dog");
+    }
+
+    #[test]
+    fn respondents_jsonl()
+    {
+        let answers = Answers::parse(
+            "\"email\", \"pets\", \"comment \\ note\"
+            \"a@x.com\", cat;dog, \"ok\"
+            \"b@x.com\", \"\", \"line\nbreak\"
+            \"c@x.com\", \"fish\", \"\"").unwrap();
+
+        let config = Config::build(["-u", "-s", "email", "--respondents-jsonl", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        let index = question_index(&config, &answers).unwrap();
+        assert_eq!(respondent_lines(&answers, index), vec![
+            "{\"uid\":\"a@x.com\",\"answers\":{\"email\":\"a@x.com\",\"pets\":[\"cat\",\"dog\"],\"comment \\\\ note\":\"ok\"}}",
+            "{\"uid\":\"b@x.com\",\"answers\":{\"email\":\"b@x.com\",\"pets\":[],\"comment \\\\ note\":\"line\\nbreak\"}}",
+            "{\"uid\":\"c@x.com\",\"answers\":{\"email\":\"c@x.com\",\"pets\":[\"fish\"],\"comment \\\\ note\":\"\"}}"
+            ]);
+    }
+
+    #[test]
+    fn avg_selections()
+    {
+        let answers = Answers::parse(
+            "\"uid\", \"pets\"
+            \"a\", cat;dog;fish
+            \"b\", \"\"
+            \"c\", dog
+            \"d\", cat;dog").unwrap();
+
+        let counts = selection_counts(answers.cells(1));
+        assert_eq!(counts, vec![3, 0, 1, 2]);
+        assert_eq!(average_count(&counts), 1.5);
+        assert_eq!(average_count(&[]), 0.0);
+    }
+
+    #[test]
+    fn word_frequencies()
+    {
+        let replies = [
+            "The app is great",
+            "great design, the colors are GREAT!",
+            "",
+            "it crashes on the login"
+        ];
+
+        let top = |skip_stopwords|
+        {
+            let words = words(replies.into_iter(), skip_stopwords);
+            distribution(words.iter().map(|word| &word[..])).into_iter()
+                .take(3)
+                .map(|(word, count)| (word.to_string(), count))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(top(false), vec![
+            (String::from("great"), 3),
+            (String::from("the"), 3),
+            (String::from("app"), 1)
+            ]);
+
+        assert_eq!(top(true), vec![
+            (String::from("great"), 3),
+            (String::from("app"), 1),
+            (String::from("colors"), 1)
+            ]);
+    }
+
+    #[test]
+    fn bounded_top_categories()
+    {
+        let replies = ["a", "x", "a", "b", "y", "a", "", "b", "z", "a"];
+
+        let top = sort_counts(top_categories(replies.into_iter().map(|reply| (reply, 1)), 2));
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].0, "a");
+        assert!(top[0].1>=4);
+
+        let exact = sort_counts(top_categories(replies.into_iter().map(|reply| (reply, 1)), 10));
+        assert_eq!(exact[..2], [(String::from("a"), 4), (String::from("b"), 2)]);
+    }
+
+    #[test]
+    fn huge_choice_weights()
+    {
+        let mut replies = vec!["q", "a:4000000000", "b:2", "a:4000000000", "c"];
+
+        let counts = strip_weights(&mut replies).unwrap();
+        assert_eq!(replies, ["q", "a", "b", "a", "c"]);
+        assert_eq!(counts, [4_000_000_000, 2, 4_000_000_000, 1]);
+
+        let top = sort_counts(top_categories(replies.into_iter().skip(1).zip(counts), 2));
+        assert_eq!(top[0], (String::from("a"), 8_000_000_000));
+
+        assert!(strip_weights(&mut ["q", "a:99999999999"]).is_err());
+    }
+
+    #[test]
+    fn five_number_summary()
+    {
+        let values = [7.0, 1.0, 3.0, 9.0, 5.0];
+
+        let summary = five_number(&values).unwrap();
+        assert_eq!(summary, [1.0, 3.0, 5.0, 7.0, 9.0]);
+        assert_eq!(boxplot(summary, 9), "|-[ | ]-|");
+
+        assert_eq!(five_number(&[1.0, 2.0, 3.0, 4.0]), Some([1.0, 1.75, 2.5, 3.25, 4.0]));
+        assert_eq!(boxplot(five_number(&[1.0, 1.0, 1.0, 10.0]).unwrap(), 10), "| ]------|");
+        assert_eq!(boxplot([2.0; 5], 10), "|");
+        assert_eq!(five_number(&[]), None);
+    }
+
+    #[test]
+    fn net_promoter_score()
+    {
+        let scores = [0.0, 6.0, 7.0, 8.0, 9.0, 10.0, 10.0, 9.0, 3.0, 10.0, 11.0, -1.0];
+
+        let counts = nps_counts(&scores);
+        assert_eq!(counts, [3, 2, 5]);
+        assert_eq!(nps(counts), Some(20.0));
+
+        assert_eq!(nps([0, 4, 0]), Some(0.0));
+        assert_eq!(nps([1, 0, 0]), Some(-100.0));
+        assert_eq!(nps(nps_counts(&[12.0])), None);
+    }
+
+    #[test]
+    fn goodness_of_fit()
+    {
+        let build = |args: &[&str]| Config::build(args.iter().map(|arg| arg.to_string()));
+
+        let config = build(&["-s", "q", "--chi-square", "--expected", "a=0.5, b=0.25,c=0.25", "file"])
+            .unwrap();
+
+        let replies = ["a", "b", "a", "c", "", "other", "a", "b", "c", "a"];
+        assert_eq!(chi_square(replies.into_iter(), &config.expected), Some((0.0, 2)));
+
+        let replies = ["a", "a", "a", "a", "a", "a", "b", "c"];
+        let (statistic, freedom) = chi_square(replies.into_iter(), &config.expected).unwrap();
+        assert_eq!((statistic, freedom), ((6.0-4.0f64).powi(2)/4.0+2.0*(1.0-2.0f64).powi(2)/2.0, 2));
+
+        let config = build(&["-s", "q", "--chi-square", "--expected", "a=1,b=3", "file"]).unwrap();
+        let replies = ["a", "b", "b", "b"];
+        assert_eq!(chi_square(replies.into_iter(), &config.expected), Some((0.0, 1)));
+        assert_eq!(chi_square(["other"].into_iter(), &config.expected), None);
+
+        assert!(build(&["-s", "q", "--chi-square", "file"]).is_err());
+        assert!(build(&["-s", "q", "--expected", "a=0,b=1", "file"]).is_err());
+    }
+
+    #[test]
+    fn cumulative_scale()
+    {
+        let replies = ["never", "often", "always", "", "often", "sometimes", "maybe", "often"];
+
+        let config = Config::build(["-s", "q", "--cumulative", "--scale", "never, sometimes,often,always",
+            "file"].into_iter().map(String::from)).unwrap();
+
+        assert_eq!(cumulative(replies.into_iter(), &config.scale), vec![
+            ("never", 100.0),
+            ("sometimes", 500.0/6.0),
+            ("often", 400.0/6.0),
+            ("always", 100.0/6.0)
+            ]);
+
+        assert!(cumulative(["", "maybe"].into_iter(), &config.scale).iter()
+            .all(|(_, percentage)| *percentage==0.0));
+
+        assert!(Config::build(["-s", "q", "--cumulative", "file"]
+            .into_iter().map(String::from)).is_err());
+    }
+
+    #[test]
+    fn zero_count_scale()
+    {
+        let scale: Vec<String> = ["1", "2", "3", "4", "5"].into_iter().map(String::from).collect();
+
+        let occurrences = frequencies(["2", "4", "4", "", "3"].into_iter());
+        assert_eq!(sort_counts(with_scale(occurrences, &scale)), vec![
+            ("4", 2), ("2", 1), ("3", 1), ("1", 0), ("5", 0)
+            ]);
+
+        let streamed = HashMap::from([(String::from("6"), 1)]);
+        assert_eq!(sort_counts(with_scale(streamed, &scale[3..])), vec![
+            (String::from("6"), 1), (String::from("4"), 0), (String::from("5"), 0)
+            ]);
+
+        let occurrences = frequencies(["a"].into_iter());
+        assert_eq!(sort_counts(with_scale(occurrences, &[])), vec![("a", 1)]);
+    }
+
+    #[test]
+    fn text_stats_lengths()
+    {
+        assert_eq!(text_stats(&[8, 2, 7, 3]), Some((2, 5.0, 8)));
+        assert_eq!(text_stats(&[]), None);
+    }
+
+    #[test]
+    fn score_histogram()
+    {
+        let bins = score_bins(&[3, 1, 3, 5, 3, 1, 10]);
+        assert_eq!(bins, vec![(1, 2), (3, 3), (5, 1), (10, 1)]);
+
+        let bins: Vec<(String, u32)> = bins.into_iter()
+            .map(|(score, count)| (score.to_string(), count))
+            .collect();
+
+        assert_eq!(histogram(&bins), vec![
+            " 1 | ## 2",
+            " 3 | ### 3",
+            " 5 | # 1",
+            "10 | # 1"
+            ]);
+
+        let wide = histogram(&[(String::from("a"), 80), (String::from("b"), 20)]);
+        assert_eq!(wide[0], format!("a | {} 80", "#".repeat(40)));
+        assert_eq!(wide[1], format!("b | {} 20", "#".repeat(10)));
+
+        let huge = histogram(&[(String::from("a"), u32::MAX), (String::from("b"), u32::MAX/2)]);
+        assert_eq!(huge[0], format!("a | {} {}", "#".repeat(40), u32::MAX));
+        assert_eq!(huge[1], format!("b | {} {}", "#".repeat(19), u32::MAX/2));
+    }
+
+    #[test]
+    fn count_per_respondent()
+    {
+        let answers = Answers::parse(
+            "\"uid\", \"pets\"
+            \"a\", cat;dog;fish;bird
+            \"b\", \"\"
+            \"c\", dog
+            \"d\", cat;dog;fish;bird
+            \"e\", \"cat\"").unwrap();
+
+        let bins = count_bins(&selection_counts(answers.cells(1)));
+        assert_eq!(bins, vec![
+            (String::from("0"), 1),
+            (String::from("1"), 2),
+            (String::from("2"), 0),
+            (String::from("3"), 0),
+            (String::from("4"), 2)
+            ]);
+
+        assert_eq!(histogram(&bins), vec!["0 | # 1", "1 | ## 2", "2 |  0", "3 |  0", "4 | ## 2"]);
+        assert_eq!(count_bins(&[]), vec![(String::from("0"), 0)]);
+    }
+
+    #[test]
+    fn streamed_frequencies()
+    {
+        let file = "\"time\", \"pick\"\r\n\"1\", \"cats\"\n\n\"2\", dogs;cats\n\
+            \"3\", \"multi\nline\"\n\"4\", \"\"\n\"5\", \"  \"\n";
+
+        let answers = Answers::parse(file).unwrap();
+        let replies = answers.question("pick").unwrap();
+
+        let expected: HashMap<String, u32> = frequencies(replies.into_iter().skip(1))
+            .into_iter()
+            .map(|(reply, count)| (reply.to_string(), count))
+            .collect();
+
+        let options = ParseOptions::default();
+        let (label, streamed) = stream_frequencies(file.as_bytes(), &options, |label| label=="pick")
+            .unwrap();
+
+        assert_eq!(label, "pick");
+        assert_eq!(streamed, expected);
+        assert_eq!(streamed.get("cats"), Some(&2));
+        assert_eq!(streamed.get("multi\nline"), Some(&1));
+        assert_eq!(streamed.get("  "), None);
+
+        // the stream rejects whatever the in memory parser rejects, with the same error
+        let stream = |file: &str, options: &ParseOptions|
+        {
+            stream_frequencies(file.as_bytes(), options, |label| label=="pick").map(|_| ())
+        };
+
+        let no_blanks = ParseOptions{allow_blank_lines: false, ..Default::default()};
+        assert_eq!(stream(file, &no_blanks).unwrap_err(), "blank line at line 3");
+        assert_eq!(Answers::parse_with(file, &no_blanks).unwrap_err(), "blank line at line 3");
+
+        let ragged = "\"time\", \"pick\"\n\"1\", \"cats\"\n\"2\"\n\"3\", \"dogs\", \"extra\"\n";
+        assert_eq!(stream(ragged, &options), Answers::parse(ragged).map(|_| ()));
+
+        let strict = ParseOptions{require_rectangular: true, ..Default::default()};
+        assert_eq!(stream(ragged, &strict).unwrap_err(), "row 2 has 1 fields, row 3 has 3 fields, expected 2");
+        assert_eq!(stream(ragged, &strict), Answers::parse_with(ragged, &strict).map(|_| ()));
+    }
+
+    #[test]
+    fn bayesian_rank()
+    {
+        let answers = Answers::parse(
+            "\"time\", \"popular\", \"niche\", \"bad\"
+            \"1\", \"5\", \"5\", \"1\"
+            \"2\", \"5\", \"\", \"1\"
+            \"3\", \"5\", \"\", \"1\"
+            \"4\", \"5\", \"\", \"1\"
+            \"5\", \"4\", \"\", \"1\"
+            \"6\", \"5\", \"\", \"1\"").unwrap();
+
+        let build = |args: &str| Config::build(args.split(' ').map(String::from)).unwrap();
+
+        let mapping = "-m ,1,1,2,2,3,3,4,4,5,5";
+
+        let labels = |ranked: Vec<(&str, Option<f64>)>| -> Vec<String>
+        {
+            ranked.into_iter().map(|(label, _)| label.to_string()).collect()
+        };
+
+        let config = build(&format!("-r {mapping} --bayesian-rank file"));
+        assert_eq!(labels(bayesian_ranked(&config, &answers)), vec!["popular", "niche", "bad"]);
+
+        let config = build(&format!("-r {mapping} --bayesian-rank --prior 2 file"));
+        let mean = 40.0/13.0;
+        assert_eq!(bayesian_ranked(&config, &answers), vec![
+            ("popular", Some((2.0*mean+29.0)/8.0)),
+            ("niche", Some((2.0*mean+5.0)/3.0)),
+            ("bad", Some((2.0*mean+6.0)/8.0))
+            ]);
+
+        assert_eq!(column_totals(&config, &answers), vec![(15, 5), (29, 6), (5, 1), (6, 6)]);
+
+        let blank = Answers::parse(
+            "\"time\", \"rated\", \"empty\"
+            \"1\", \"4\", \"\"
+            \"2\", \"2\", \" \"").unwrap();
+
+        let ranked = bayesian_ranked(&config, &blank);
+        assert_eq!(ranked, vec![("rated", Some((2.0*3.0+6.0)/4.0)), ("empty", None)]);
+        assert_eq!(ranked_average(ranked[1].1), "no responses");
+    }
+
+    #[test]
+    fn ranked_no_responses()
+    {
+        let answers = Answers::parse(
+            "\"time\", \"q1\", \"q2\", \"q3\", \"comment\"
+            \"1\", \"yes\", \"\", \"no\", \"great\"
+            \"2\", \"no\", \"\", \"no\", \"too long\"").unwrap();
+
+        let config = Config::build(["-r", "-m", ",yes,1,no,0", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        let label_sums = ranked(&config, &answers);
+        assert_eq!(label_sums, vec![
+            ("q1", Some(0.5)), ("q3", Some(0.0)), ("comment", Some(0.0)), ("q2", None)
+            ]);
+
+        // the --state path agrees on unmapped answers
+        let aggregate = Aggregate::from_answers(&answers, |label| column_mappings(&config, label));
+        assert_eq!(aggregate.ranked(), label_sums);
+
+        let lines: Vec<String> = label_sums.into_iter()
+            .map(|(label, average)| format!("{label}: {}", ranked_average(average)))
+            .collect();
+
+        assert_eq!(lines, vec![
+            "q1: average 0.50", "q3: average 0.00", "comment: average 0.00", "q2: no responses"
+            ]);
+    }
+
+    #[test]
+    fn min_average()
+    {
+        let label_sums = vec![
+            ("q1", Some(4.5)),
+            ("q2", Some(4.0)),
+            ("q3", Some(3.9)),
+            ("q4", Some(1.0)),
+            ("q5", None)
+            ];
+
+        let config = Config::build(["-r", "--min-average", "4.0", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        assert_eq!(filter_ranked(&config, label_sums.clone()), vec![("q1", Some(4.5)), ("q2", Some(4.0))]);
+
+        let config = Config::build(["-r", "file"].into_iter().map(String::from)).unwrap();
+        assert_eq!(filter_ranked(&config, label_sums.clone()), label_sums);
+    }
+
+    #[test]
+    fn top_percent()
+    {
+        assert_eq!(top_count(10, 25.0), 3);
+        assert_eq!(top_count(10, 30.0), 3);
+        assert_eq!(top_count(3, 10.0), 1);
+        assert_eq!(top_count(4, 100.0), 4);
+        assert_eq!(top_count(0, 50.0), 0);
+
+        let label_sums = vec![("q1", Some(4.5)), ("q2", Some(4.0)), ("q3", Some(3.9)), ("q4", None)];
+
+        let build = |args: &[&str]| Config::build(args.iter().map(|arg| arg.to_string()));
+
+        let config = build(&["-r", "--top-percent", "50", "file"]).unwrap();
+        assert_eq!(filter_ranked(&config, label_sums.clone()), vec![("q1", Some(4.5)), ("q2", Some(4.0))]);
+
+        let config = build(&["-r", "--top-percent", "1", "file"]).unwrap();
+        assert_eq!(filter_ranked(&config, label_sums.clone()), vec![("q1", Some(4.5))]);
+
+        assert!(build(&["-r", "--top-percent", "0", "file"]).is_err());
+        assert!(build(&["-r", "--top-percent", "100.5", "file"]).is_err());
+        assert!(build(&["-r", "--top-percent", "NaN", "file"]).is_err());
+    }
+
+    #[test]
+    fn fail_on_empty()
+    {
+        let replies = vec!["blank question", "", "", ""];
+
+        let config = Config::build(["-s", "blank", "file"].into_iter().map(String::from)).unwrap();
+        assert!(print_normal(&config, replies.clone(), None, None).is_ok());
+
+        let config = Config::build(["-s", "blank", "--fail-on-empty", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        let error = print_normal(&config, replies, None, None).unwrap_err();
+        assert_eq!(error.to_string(), "blank question has no responses");
+
+        assert!(print_normal(&config, vec!["question", "", "yes"], None, None).is_ok());
+
+        let config = Config::build(["-s", "blank", "--shell", "--fail-on-empty", "file"]
+            .into_iter().map(String::from)).unwrap();
+
+        assert!(print_normal(&config, vec!["blank question", ""], None, None).is_err());
+    }
+
+    #[test]
+    fn analyze_fixture_file()
+    {
+        let input = std::env::temp_dir().join("quizanalyze_analyze_input.csv");
+        fs::write(&input, "\"time\", \"q1\"\n\"1\", \"yes\"\n\"2\", \"no\"\n\"3\", \"yes\"").unwrap();
+
+        let path = input.to_str().unwrap();
+
+        let config = Config::build(["-s", "q1", "-m", ",yes,1,no,0", path]
+            .into_iter().map(String::from)).unwrap();
+
+        let report = analyze_file(path, &config).unwrap();
+        assert_eq!(report, AnalysisReport{questions: vec![QuestionStats{
+            label: String::from("q1"),
+            responses: 3,
+            most_popular: Some(String::from("yes")),
+            average: Some(2.0/3.0),
+            median: Some(1.0)
+        }]});
+
+        let config = Config::build(["--oneline", path].into_iter().map(String::from)).unwrap();
+
+        let report = analyze_file(path, &config).unwrap();
+        let labels: Vec<&str> = report.questions.iter().map(|stats| &stats.label[..]).collect();
+
+        assert_eq!(labels, vec!["time", "q1"]);
+        assert_eq!(report.questions[1].average, None);
+
+        let config = Config::build(["-s", "q2", path].into_iter().map(String::from)).unwrap();
+        assert!(analyze_file(path, &config).is_err());
+    }
+
+    #[test]
+    fn gzip_input()
+    {
+        use std::io::Write;
+        use flate2::{Compression, write::GzEncoder};
+
+        let text = "\"time\", \"q1\"\n\"1\", \"yes\"\n";
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+
+        let read = |bytes: Vec<u8>|
+        {
+            let mut out = String::new();
+            decompressed(io::Cursor::new(bytes)).unwrap().read_to_string(&mut out).unwrap();
+
+            out
+        };
+
+        assert_eq!(read(compressed), text);
+        assert_eq!(read(text.as_bytes().to_vec()), text);
+        assert_eq!(read(Vec::new()), "");
+    }
+
+    #[test]
+    fn stamp_line()
+    {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(951782400), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(1709251199), "2024-02-29T23:59:59Z");
+
+        let time = UNIX_EPOCH+std::time::Duration::from_secs(1718454896);
+        assert_eq!(stamp("data/answers.csv", time), "# data/answers.csv analyzed at 2024-06-15T12:34:56Z");
+    }
+
+    #[test]
+    fn parse_stats_summary()
+    {
+        let config = Config::build(["--parse-stats", "-s", "q1", "file"].iter().map(|arg| arg.to_string())).unwrap();
+        let file = "\"q1\"\n\n\"a\"\n\"b\"\n  \n";
+
+        let (_, stats) = prepare(&config, file, Instant::now()).unwrap();
+        assert_eq!(parse_stats_line(stats), "read 5 lines, skipped 2 blank, kept 2 rows");
+    }
+
+    #[test]
+    fn error_variants()
+    {
+        let build = |args: &[&str]| Config::build(args.iter().map(|arg| arg.to_string()));
+
+        let error = build(&["--stats", "mode", "-s", "q1", "file"]).err().unwrap();
+        assert!(matches!(error, Error::Config(_)));
+        assert_eq!(error.to_string(), "unknown stat mode, expected one of average, median, iqr, std_dev, cv");
+
+        let config = build(&["-s", "q1", "file"]).unwrap();
+
+        let missing = std::env::temp_dir().join("quizanalyze_missing_input.csv");
+        let _ = fs::remove_file(&missing);
+        match analyze_file(missing.to_str().unwrap(), &config)
+        {
+            Err(Error::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::NotFound),
+            other => panic!("expected an io error, got {other:?}")
+        }
+
+        let error = analyze_str("\"q1\", \"q2\"\n\"a\"", &config).unwrap_err();
+        assert!(matches!(error, Error::Parse(_)));
+        assert_eq!(error.to_string(), "parse error: replies are not the same size as labels");
+
+        let config = build(&["-s", "q3", "file"]).unwrap();
+        assert!(matches!(analyze_str("\"q1\"\n\"a\"", &config), Err(Error::Analysis(_))));
+
+        let config = build(&["-s", "q1", "--require-column", "q2", missing.to_str().unwrap()]).unwrap();
+        fs::write(&missing, "\"q1\"\n\"a\"").unwrap();
+        assert!(matches!(run(&config), Err(Error::Config(_))));
+        fs::remove_file(&missing).unwrap();
+    }
+
+    #[test]
+    fn report_file()
+    {
+        let directory = std::env::temp_dir();
+        let input = directory.join("quizanalyze_report_input.csv");
+        let output = directory.join("quizanalyze_report_output.md");
+
+        fs::write(&input, "\"time\", \"q1\", \"q2\"\n\"1\", \"yes\", \"no\"\n\"2\", \"no\", \"no\"")
+            .unwrap();
+
+        let args = ["-m", ",yes,1,no,0", "--report", output.to_str().unwrap(), input.to_str().unwrap()];
+        let config = Config::build(args.into_iter().map(String::from)).unwrap();
+
+        run(&config).unwrap();
+
+        let report = fs::read_to_string(&output).unwrap();
+        for heading in ["# Report", "## Summary", "## Distributions", "### q1", "### q2", "## Ranking"]
+        {
+            assert!(report.lines().any(|line| line==heading), "missing {heading}");
+        }
+
+        assert!(report.contains("| q1 | 2 | "));
+        assert!(report.contains("1. q1: average 0.50"));
+        assert!(report.contains("2. q2: average 0.00"));
+    }
+
+    #[test]
+    fn stream_errors()
+    {
+        let input = std::env::temp_dir().join("quizanalyze_stream_errors.csv");
+        fs::write(&input, "\"q1\", \"q2\"\n\"a\", \"b\"\n\"c\"\n").unwrap();
+
+        let build = |search: &str| Config::build(["--stream", "-s", search, input.to_str().unwrap()]
+            .into_iter().map(String::from)).unwrap();
+
+        assert!(matches!(run(&build("q2")), Err(Error::Parse(_))));
+        assert!(matches!(run(&build("missing")), Err(Error::Analysis(_))));
+    }
+
+    #[test]
+    fn oversized_weights()
+    {
+        let input = std::env::temp_dir().join("quizanalyze_oversized_weights.csv");
+        fs::write(&input, "\"q\"\n\"a:4000000000\"\n\"a:4000000000\"\n\"b:99999999999\"\n").unwrap();
+
+        for flags in [&["--stream"][..], &["--max-categories", "2"], &[]]
+        {
+            let args = flags.iter().copied().chain(["--weighted-choices", "-s", "q", input.to_str().unwrap()]);
+            let config = Config::build(args.map(String::from)).unwrap();
+
+            assert!(matches!(run(&config), Err(Error::Analysis(_))));
+        }
+    }
+
+    #[test]
+    fn collate_file()
+    {
+        let directory = std::env::temp_dir();
+        let input = directory.join("quizanalyze_collate_input.csv");
+        let output = directory.join("quizanalyze_collate_output.csv");
+
+        fs::write(&input, "\"id\", \"q1\"\n\"1\", \" yes  please \"\n\"2\", a;b;a\n\"1\", \"no\"").unwrap();
+
+        let args = ["--collapse-whitespace", "--dedupe-answers", "--dedupe-uid", "--uid-col", "0",
+            "--collate", output.to_str().unwrap(), input.to_str().unwrap()];
+        let config = Config::build(args.into_iter().map(String::from)).unwrap();
+
+        run(&config).unwrap();
+
+        let collated = Answers::parse(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(collated, Answers::parse("\"id\", \"q1\"\n\"2\", a;b\n\"1\", \"no\"").unwrap());
+    }
+}
+
+mod csv;
+pub use csv::csv_reader::{Answers, ColumnType, ParseOptions, ParseStats, stream_frequencies};
+use csv::csv_reader::{stream_choices, split_weight};
+
+mod aggregate;
+use aggregate::Aggregate;
+
+mod analysis;
+pub use analysis::{AnalysisReport, QuestionStats};
+
+mod random;
+use random::Rng;
+
+mod crosstab;
+use crosstab::CrossTab;
+
+mod space_saving;
+use space_saving::SpaceSaving;
+
+mod json;
+
+mod error;
+pub use error::Error;
+
+pub fn run(config: &Config) -> Result<(), Error>
+{
+    run_mode(config)?;
+
+    // the report file gets its own stamp
+    if config.stamp && config.report.is_none()
+    {
+        println!("{}", stamp(&config.filepath, SystemTime::now()));
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn run_mode(config: &Config) -> Result<(), Error>
+{
+    if config.json_schema
+    {
+        println!("{}", AnalysisReport::json_schema());
+        return Ok(());
+    }
+
+    if config.mapping_report
+    {
+        mapping_lines(&config.mappings).iter().for_each(|line| println!("{line}"));
+
+        if !config.asks_question()
+        {
+            return Ok(());
+        }
+
+        println!();
+    }
+
+    if config.stream
+    {
+        return print_stream(config);
+    }
+
+    // the timeout includes reading the file
+    let start = Instant::now();
+    let file = read_input(&config.filepath)?;
+
+    let (answers, stats) = prepare(config, &file, start)?;
+
+    if config.parse_stats
+    {
+        println!("{}", parse_stats_line(stats));
+    }
+
+    if let Some(path) = &config.collate
+    {
+        fs::write(path, answers.to_csv())?;
+    }
+
+    if config.list_columns
+    {
+        column_lines(&answers).iter().for_each(|line| println!("{line}"));
+        return Ok(());
+    }
+
+    if let Some(path) = &config.report
+    {
+        let mut text = report(config, &answers);
+        if config.stamp
+        {
+            text.push_str(&format!("\n{}\n", stamp(&config.filepath, SystemTime::now())));
+        }
+
+        fs::write(path, text)?;
+        return Ok(());
+    }
+
+    if config.json
+    {
+        println!("{}", AnalysisReport::new(config, &answers).map_err(Error::Analysis)?.to_json());
+        return Ok(());
+    }
+
+    if let Some((question, low, high)) = &config.assert_average
+    {
+        println!("{}", check_average(config, &answers, question, *low, *high).map_err(Error::Analysis)?);
+        return Ok(());
+    }
+
+    if let Some(column) = &config.duration_column
+    {
+        return print_durations(config, &answers, column);
+    }
+
+    if let Some(question) = &config.trend
+    {
+        return print_trend(config, &answers, question);
+    }
+
+    if let Some((first, second)) = &config.jaccard
+    {
+        return print_jaccard(config, &answers, first, second);
+    }
+
+    if config.mapping_coverage
+    {
+        for (label, coverage) in mapping_coverage(config, &answers)
+        {
+            println!("{}: {coverage:.1}% mapped", heading(config, answers.labels(), label));
+        }
+
+        return Ok(());
+    }
+
+    if config.oneline
+    {
+        oneline(config, &answers).iter().for_each(|line| println!("{line}"));
+        return Ok(());
+    }
+
+    // only collating, there is no question to analyze
+    let asked = config.rank || !config.search.is_empty() || config.column.is_some()
+        || (config.unique && config.uid_column.is_some());
+    if config.collate.is_some() && !asked
+    {
+        return Ok(());
+    }
+
+    if !config.rank
+    {
+        let mut replies = question(config, &answers).map_err(Error::Analysis)?;
+
+        let label = replies[0];
+        let heading = heading(config, answers.labels(), label);
+        replies[0] = &heading;
+
+        let counts = if config.weighted_choices
+        {
+            Some(strip_weights(&mut replies).map_err(Error::Analysis)?)
+        } else
+        {
+            None
+        };
+
+        if let Some(group) = &config.group_by
+        {
+            print_groups(config, &answers, group)
+        } else if let Some((column, first, second)) = &config.compare_groups
+        {
+            print_comparison(config, &answers, column, [first, second])
+        } else if let Some(column) = &config.crosstab
+        {
+            print_crosstab(config, &answers, column)
+        } else if config.avg_selections
+        {
+            print_avg_selections(config, &answers)
+        } else if config.validate_numeric
+        {
+            print_validate_numeric(config, &answers, label, replies[0])
+        } else if config.chi_square
+        {
+            print_chi_square(config, replies)
+        } else if config.nps
+        {
+            print_nps(config, replies)
+        } else if config.boxplot
+        {
+            print_boxplot(config, replies)
+        } else if config.cumulative
+        {
+            print_cumulative(config, replies)
+        } else if config.text_stats
+        {
+            print_text_stats(&answers, label, replies[0])
+        } else if let Some(amount) = config.word_freq
+        {
+            print_word_freq(config, replies, amount)
+        } else if let Some(amount) = config.max_categories
+        {
+            print_top_categories(replies, amount, counts)
+        } else if config.count_per_respondent
+        {
+            print_count_histogram(config, &answers)
+        } else if config.score_histogram
+        {
+            print_score_histogram(config, replies)
+        } else if let Some(value) = &config.breakdown
+        {
+            print_breakdown(value, replies)
+        } else if config.respondents_jsonl
+        {
+            let index = question_index(config, &answers).map_err(Error::Analysis)?;
+            respondent_lines(&answers, index).iter().for_each(|line| println!("{line}"));
+
+            Ok(())
+        } else if config.unique
+        {
+            print_unique(config, &answers, replies)
+        } else
+        {
+            let merged = config.merge_separator.as_ref().map(|separator|
+            {
+                let index = question_index(config, &answers).expect("question was found");
+                merged_replies(answers.cells(index), separator)
+            });
+
+            let weights = config.weight.as_ref().map(|column|
+            {
+                let index = question_index(config, &answers).map_err(Error::Analysis)?;
+                let weight_index = answers.index_of(column)
+                    .ok_or_else(|| Error::Analysis(format!("cant find {column}")))?;
+
+                choice_weights(&answers, index, weight_index)
+            }).transpose()?;
+
+            let weights = match (weights, counts)
+            {
+                (Some(weights), Some(counts)) =>
+                {
+                    Some(weights.iter().zip(counts).map(|(weight, count)| weight * count as f64).collect())
+                },
+                (weights, None) => weights,
+                (None, Some(counts)) => Some(counts.into_iter().map(|count| count as f64).collect())
+            };
+
+            print_normal(config, replies, merged, weights)
+        }
+    } else if let Some(path) = &config.state
+    {
+        print_ranked_state(config, &answers, path)
+    } else
+    {
+        print_ranked(config, answers)
+    }
+}
+
+fn stamp(filepath: &str, time: SystemTime) -> String
+{
+    let seconds = time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+
+    format!("# {filepath} analyzed at {}", rfc3339(seconds))
+}
+
+// utc time from seconds since the unix epoch
+fn rfc3339(seconds: u64) -> String
+{
+    let days = (seconds/86400) as i64;
+    let time = seconds%86400;
+
+    // civil date from days since the epoch, shifted to start the year in march
+    let shifted = days+719468;
+    let era = shifted.div_euclid(146097);
+    let day_of_era = shifted.rem_euclid(146097);
+    let year_of_era = (day_of_era-day_of_era/1460+day_of_era/36524-day_of_era/146096)/365;
+    let day_of_year = day_of_era-(365*year_of_era+year_of_era/4-year_of_era/100);
+    let month_index = (5*day_of_year+2)/153;
+
+    let day = day_of_year-(153*month_index+2)/5+1;
+    let month = if month_index<10 { month_index+3 } else { month_index-9 };
+    let year = year_of_era+era*400+i64::from(month<=2);
+
+    format!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z", time/3600, time%3600/60, time%60)
+}
+
+pub fn analyze_file(path: &str, config: &Config) -> Result<AnalysisReport, Error>
+{
+    let start = Instant::now();
+    analyze_since(&read_input(path)?, config, start)
+}
+
+// - reads from stdin
+#[allow(clippy::suspicious_else_formatting)]
+fn open_input(path: &str) -> io::Result<Box<dyn BufRead>>
+{
+    if path=="-"
+    {
+        decompressed(io::stdin().lock())
+    } else
+    {
+        decompressed(BufReader::new(fs::File::open(path)?))
+    }
+}
+
+// gzip is detected by its magic bytes so piped input works too
+#[allow(clippy::suspicious_else_formatting)]
+fn decompressed<R: BufRead + 'static>(mut reader: R) -> io::Result<Box<dyn BufRead>>
+{
+    if reader.fill_buf()?.starts_with(&[0x1f, 0x8b])
+    {
+        Ok(Box::new(BufReader::new(GzDecoder::new(reader))))
+    } else
+    {
+        Ok(Box::new(reader))
+    }
+}
+
+fn read_input(path: &str) -> io::Result<String>
+{
+    let mut file = String::new();
+    open_input(path)?.read_to_string(&mut file)?;
+
+    Ok(file)
+}
+
+pub fn analyze_str(file: &str, config: &Config) -> Result<AnalysisReport, Error>
+{
+    analyze_since(file, config, Instant::now())
+}
+
+fn analyze_since(file: &str, config: &Config, start: Instant) -> Result<AnalysisReport, Error>
+{
+    let (answers, _) = prepare(config, file, start)?;
+
+    AnalysisReport::new(config, &answers).map_err(Error::Analysis)
+}
+
+// parses the file and applies all the filtering and normalizing options, the timeout
+// counts from start
+fn prepare(
+    config: &Config,
+    file: &str,
+    start: Instant
+) -> Result<(Answers, ParseStats), Error>
+{
+    let (mut answers, stats) = Answers::parse_since(file, &config.parse_options, start)
+        .map_err(Error::Parse)?;
+
+    check_required(&answers, &config.required_columns).map_err(Error::Config)?;
+
+    if config.dedupe_uid
+    {
+        let uid_column = config.uid_column.expect("checked when building config");
+
+        let index = answers.resolve_index(uid_column as isize)
+            .ok_or_else(|| Error::Config(format!("no uid column at index {uid_column}")))?;
+
+        answers.dedupe_by(index);
+    }
+
+    if let Some((start, end)) = config.range
+    {
+        answers.retain_range(start, end);
+    }
+
+    if let Some(column) = &config.balance
+    {
+        let index = answers.index_of(column).ok_or_else(|| Error::Analysis(format!("cant find {column}")))?;
+        let per_group = config.per_group.expect("checked when building config");
+
+        let indices = balanced_indices(&answers, index, per_group, &mut rng(config));
+        answers.retain_indices(&indices);
+    }
+
+    if config.collapse_whitespace
+    {
+        answers.collapse_whitespace();
+    }
+
+    if config.dedupe_answers
+    {
+        answers.dedupe_choices();
+    }
+
+    if config.sanitize
+    {
+        answers.map_all(sanitize);
+    }
+
+    if let Some(column) = &config.cohort
+    {
+        let index = answers.index_of(column).ok_or_else(|| Error::Analysis(format!("cant find {column}")))?;
+
+        let indices = cohort_indices(&answers, index);
+        answers.retain_indices(&indices);
+    }
+
+    if let Some(label) = &config.sort_by
+    {
+        answers.sort_respondents_with(label, |text| parse_number(text, config.loose_numbers))
+            .map_err(Error::Analysis)?;
+    }
+
+    // rows dropped by the filters above arent kept either
+    let rows = answers.replies().len();
+
+    Ok((answers, ParseStats{rows, ..stats}))
+}
+
+fn parse_stats_line(stats: ParseStats) -> String
+{
+    format!("read {} lines, skipped {} blank, kept {} rows", stats.lines, stats.skipped, stats.rows)
+}
+
+// respondents who picked the most popular answer to the question
+fn cohort_indices(answers: &Answers, index: usize) -> Vec<usize>
+{
+    let popular = match mode(answers.cells(index).flatten().map(|choice| &choice[..]), None)
+    {
+        Some(popular) => popular,
+        None => return Vec::new()
+    };
+
+    answers.cells(index).enumerate()
+        .filter(|(_, cell)| cell.iter().any(|choice| choice==popular))
+        .map(|(reply_index, _)| reply_index)
+        .collect()
+}
+
+fn question<'a>(config: &Config, answers: &'a Answers) -> Result<Vec<&'a str>, String>
+{
+    let index = question_index(config, answers)?;
+    Ok(answers.question_by_index(index as isize).expect("index is resolved"))
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn question_index(config: &Config, answers: &Answers) -> Result<usize, String>
+{
+    let uid_column = config.uid_column.filter(|_| config.unique);
+
+    if let Some(index) = uid_column
+    {
+        answers.resolve_index(index as isize)
+            .ok_or_else(|| format!("no uid column at index {index}"))
+    } else if let Some(index) = config.column
+    {
+        answers.resolve_index(index)
+            .ok_or_else(|| format!("no column at index {index}"))
+    } else
+    {
+        if config.exact
+        {
+            answers.index_of_exact(&config.search)
+        } else
+        {
+            answers.index_of(&config.search)
+        }.ok_or_else(|| format!("cant find {}", &config.search))
+    }
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn check_average(
+    config: &Config,
+    answers: &Answers,
+    question: &str,
+    low: f64,
+    high: f64) -> Result<String, String>
+{
+    if config.mappings.is_empty()
+    {
+        return Err(String::from("average assertion needs mappings"));
+    }
+
+    let replies = answers.question(question).ok_or(format!("cant find {question}"))?;
+
+    let mapped = map_replies(replies.iter().skip(1).copied(), &column_mappings(config, replies[0]));
+    let average = average(&mapped);
+
+    let message = format!("average of {} is {average:.2}", replies[0]);
+    if (low..=high).contains(&average)
+    {
+        Ok(format!("{message}, within {low}-{high}"))
+    } else
+    {
+        Err(format!("{message}, outside {low}-{high}"))
+    }
+}
+
+#[derive(PartialEq, Debug)]
+struct DurationStats
+{
+    average: f64,
+    median: f64,
+    threshold: f64,
+    fast: usize
+}
+
+fn print_durations(config: &Config, answers: &Answers, column: &str) -> Result<(), Error>
+{
+    let index = answers.index_of(column).ok_or_else(|| Error::Analysis(format!("cant find {column}")))?;
+
+    let durations = durations(answers, index, config.loose_numbers);
+    let stats = duration_stats(&durations, config.min_duration)
+        .ok_or_else(|| Error::Analysis(String::from("no numeric durations found")))?;
+
+    println!("{}", column_heading(config, index, &answers.labels()[index]));
+    println!("average: {:.2}s, median: {:.2}s", stats.average, stats.median);
+    println!("{} responses faster than {:.2}s", stats.fast, stats.threshold);
+
+    Ok(())
+}
+
+fn print_trend(config: &Config, answers: &Answers, question: &str) -> Result<(), Error>
+{
+    let index = answers.index_of(question).ok_or_else(|| Error::Analysis(format!("cant find {question}")))?;
+
+    println!("{}", column_heading(config, index, &answers.labels()[index]));
+    for (bucket, average) in trend(config, answers, index).map_err(Error::Analysis)?
+    {
+        println!("{bucket}: average {average:.2}");
+    }
+
+    Ok(())
+}
+
+// the date column is the first one (usually the timestamp) unless --date-col is given
+fn trend(config: &Config, answers: &Answers, index: usize) -> Result<Vec<(String, f64)>, String>
+{
+    if config.mappings.is_empty()
+    {
+        return Err(String::from("--trend needs a mapping"));
+    }
+
+    let date_index = match &config.date_column
+    {
+        Some(column) => answers.index_of(column).ok_or(format!("cant find {column}"))?,
+        None => 0
+    };
+
+    let mut buckets: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for (date, replies) in answers.group_by(date_index, index)
+    {
+        let date = parse_date(date).ok_or(format!("cant parse date {date}"))?;
+
+        buckets.entry(date_bucket(date, &config.bucket)).or_default().extend(replies);
+    }
+
+    let mappings = column_mappings(config, &answers.labels()[index]);
+
+    Ok(buckets.into_iter().filter_map(|(bucket, replies)|
+    {
+        let mapped = map_replies(replies.into_iter(), &mappings);
+
+        (!mapped.is_empty()).then(|| (bucket, average(&mapped)))
+    }).collect())
+}
+
+// year-month-day or month/day/year, anything after the date (like a time) is ignored
+fn parse_date(text: &str) -> Option<(u32, u32, u32)>
+{
+    let date = text.split_whitespace().next()?;
+    let parts: Vec<&str> = date.split(['-', '/', '.']).collect();
+
+    let (year, month, day) = match parts[..]
+    {
+        [year, month, day] if year.len()==4 => (year, month, day),
+        [month, day, year] if year.len()==4 => (year, month, day),
+        _ => return None
+    };
+
+    let date = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+
+    ((1..=12).contains(&date.1) && (1..=days_in_month(date.0, date.1)).contains(&date.2))
+        .then_some(date)
+}
+
+fn days_in_month(year: u32, month: u32) -> u32
+{
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+
+    match month
+    {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+fn date_bucket((year, month, day): (u32, u32, u32), bucket: &str) -> String
+{
+    match bucket
+    {
+        "day" => format!("{year}-{month:02}-{day:02}"),
+        "month" => format!("{year}-{month:02}"),
+        "year" => year.to_string(),
+        _ => unreachable!("buckets are checked when building the config")
+    }
+}
+
+// percentage of every columns non blank answers the mappings know, worst first
+fn mapping_coverage<'a>(config: &Config, answers: &'a Answers) -> Vec<(&'a str, f64)>
+{
+    let mut coverage: Vec<(&str, f64)> = answers.iter_questions().filter_map(|replies|
+    {
+        let label = replies[0];
+        let answered = replies.iter().skip(1).filter(|text| !is_blank(text)).count();
+        let mapped = map_replies(replies.into_iter().skip(1), &column_mappings(config, label)).len();
+
+        (answered!=0).then(|| (label, mapped as f64/answered as f64*100.0))
+    }).collect();
+
+    coverage.sort_by(|other, current| other.1.total_cmp(&current.1));
+
+    coverage
+}
+
+fn print_jaccard(config: &Config, answers: &Answers, first: &str, second: &str) -> Result<(), Error>
+{
+    let first = answers.index_of(first).ok_or_else(|| Error::Analysis(format!("cant find {first}")))?;
+    let second = answers.index_of(second).ok_or_else(|| Error::Analysis(format!("cant find {second}")))?;
+
+    println!("{} and {}", column_heading(config, first, &answers.labels()[first]),
+        column_heading(config, second, &answers.labels()[second]));
+    match jaccard(answers.cells(first).zip(answers.cells(second)))
+    {
+        Some((similarity, amount)) =>
+        {
+            println!("average jaccard similarity: {similarity:.2} over {amount} respondents");
+        },
+        None => println!("nobody answered either question")
+    }
+
+    Ok(())
+}
+
+// average overlap of every respondent's two sets of choices, skipping ones with both empty
+fn jaccard<'a>(cells: impl Iterator<Item=(&'a [String], &'a [String])>) -> Option<(f64, usize)>
+{
+    let set = |cell: &'a [String]| -> HashSet<&'a str>
+    {
+        cell.iter().filter(|choice| !is_blank(choice)).map(|choice| &choice[..]).collect()
+    };
+
+    let similarities: Vec<f64> = cells.filter_map(|(first, second)|
+    {
+        let (first, second) = (set(first), set(second));
+
+        let union = first.union(&second).count();
+        (union!=0).then(|| first.intersection(&second).count() as f64/union as f64)
+    }).collect();
+
+    if similarities.is_empty()
+    {
+        return None;
+    }
+
+    Some((similarities.iter().sum::<f64>()/similarities.len() as f64, similarities.len()))
+}
+
+fn durations(answers: &Answers, index: usize, loose: bool) -> Vec<f64>
+{
+    answers.replies().iter()
+        .filter_map(|reply| reply[index].first())
+        .filter_map(|text| parse_number(text, loose))
+        .collect()
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn parse_number(text: &str, loose: bool) -> Option<f64>
+{
+    if loose
+    {
+        parse_loose_number(text)
+    } else
+    {
+        text.trim().parse().ok()
+    }
+}
+
+// skips currency symbols around the number and thousands separators inside it, anything
+// else makes it not a number, a comma followed by anything but exactly three digits is
+// a decimal comma
+#[allow(clippy::suspicious_else_formatting)]
+fn parse_loose_number(text: &str) -> Option<f64>
+{
+    let outside = |c: char| c.is_whitespace() || matches!(c, '$' | '€' | '£' | '¥' | '₹' | '₽' | '¢');
+
+    let text = text.trim_matches(outside);
+    let (sign, digits) = match text.strip_prefix(['-', '+'])
+    {
+        Some(rest) => (&text[..1], rest.trim_start_matches(outside)),
+        None => ("", text)
+    };
+
+    let chars: Vec<char> = digits.chars().collect();
+    let grouped = |index: usize|
+    {
+        index>0 && chars[index-1].is_ascii_digit()
+            && chars.len()>index+3 && chars[index+1..index+4].iter().all(char::is_ascii_digit)
+            && !chars.get(index+4).is_some_and(char::is_ascii_digit)
+    };
+
+    let mut cleaned = String::from(sign);
+    for (index, c) in chars.iter().copied().enumerate()
+    {
+        if c.is_ascii_digit() || matches!(c, ',' | '.')
+        {
+            cleaned.push(c);
+        } else if !(c.is_whitespace() && grouped(index))
+        {
+            return None;
+        }
+    }
+
+    let decimal = match (cleaned.rfind(','), cleaned.rfind('.'))
+    {
+        (Some(comma), Some(dot)) => Some(comma.max(dot)),
+        (Some(comma), None) =>
+        {
+            let single = cleaned.matches(',').count()==1;
+            (single && cleaned.len()-comma-1!=3).then_some(comma)
+        },
+        (None, Some(dot)) => (cleaned.matches('.').count()==1).then_some(dot),
+        (None, None) => None
+    };
+
+    let number: String = cleaned.char_indices().filter_map(|(index, c)|
+    {
+        if Some(index)==decimal
+        {
+            Some('.')
+        } else
+        {
+            (!matches!(c, ',' | '.')).then_some(c)
+        }
+    }).collect();
+
+    number.parse().ok()
+}
+
+// without an explicit threshold anything under half the median counts as too fast
+#[allow(clippy::suspicious_else_formatting)]
+fn duration_stats(durations: &[f64], threshold: Option<f64>) -> Option<DurationStats>
+{
+    if durations.is_empty()
+    {
+        return None;
+    }
+
+    let mut sorted = durations.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    let middle = sorted.len()/2;
+    let median = if sorted.len().is_multiple_of(2)
+    {
+        (sorted[middle-1]+sorted[middle])/2.0
+    } else
+    {
+        sorted[middle]
+    };
+
+    let average = sorted.iter().sum::<f64>()/sorted.len() as f64;
+
+    let threshold = threshold.unwrap_or(median/2.0);
+    let fast = sorted.iter().filter(|duration| **duration<threshold).count();
+
+    Some(DurationStats{average, median, threshold, fast})
+}
+
+// shows control characters in caret notation so they cant mess with the terminal
+fn sanitize(text: &str) -> String
+{
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars()
+    {
+        match c
+        {
+            '\u{0}'..='\u{1f}' =>
+            {
+                out.push('^');
+                out.push(char::from(c as u8+b'@'));
+            },
+            '\u{7f}' => out.push_str("^?"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c)
+        }
+    }
+
+    out
+}
+
+fn column_lines(answers: &Answers) -> Vec<String>
+{
+    answers.labels().iter().enumerate()
+        .map(|(index, label)| format!("{index}: {label}"))
+        .collect()
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn check_required(answers: &Answers, required: &[String]) -> Result<(), String>
+{
+    let missing: Vec<&str> = required.iter()
+        .filter(|label| !answers.has_label(label))
+        .map(|label| &label[..])
+        .collect();
+
+    if missing.is_empty()
+    {
+        Ok(())
+    } else
+    {
+        Err(format!("missing required columns: {}", format_replies(missing.into_iter())))
+    }
+}
+
+// merged has the choices of every respondent joined together for display
+#[allow(clippy::suspicious_else_formatting)]
+fn print_normal(
+    config: &Config,
+    replies: Vec<&str>,
+    merged: Option<Vec<String>>,
+    weights: Option<Vec<f64>>
+) -> Result<(), Error>
+{
+    let label = replies[0];
+
+    let no_label_replies = replies.iter().skip(1).copied();
+    let mode = mode(no_label_replies.clone(), weights.as_deref());
+
+    if mode.is_none() && config.fail_on_empty
+    {
+        return Err(Error::Analysis(format!("{label} has no responses")));
+    }
+
+    if config.shell
+    {
+        print!("{}", shell_output(config, &replies));
+        return Ok(());
+    }
+
+    println!("{label}");
+    println!("most popular: {}", mode.unwrap_or("none"));
+
+    if !config.mappings.is_empty()
+    {
+        let mapped: Vec<i32> = map_replies(no_label_replies.clone(),
+            &column_mappings(config, label));
+
+        let stats: Vec<String> = config.stats.iter()
+            .map(|name| format!("{name}: {:.2}", stat(name, &mapped)))
+            .collect();
+
+        println!("{}", stats.join(", "));
+
+        if let Some(iterations) = config.bootstrap
+        {
+            if let Some((low, high)) = bootstrap(&mapped, iterations, &mut rng(config))
+            {
+                println!("95% bootstrap interval: {low:.2} - {high:.2}");
+            }
+        }
+    }
+
+    // the scale stands in for a mapping when there is none
+    let ordinal = if config.mappings.is_empty()
+    {
+        Cow::Owned(scale_mappings(&config.scale))
+    } else
+    {
+        column_mappings(config, label)
+    };
+
+    let mapped = map_replies(no_label_replies.clone(), &ordinal);
+    if !mapped.is_empty()
+    {
+        if let Some(answer) = median_label(&ordinal, median(&mapped))
+        {
+            println!("median answer: {answer}");
+        }
+    }
+
+    let display_replies = no_label_replies.clone().filter(|text| !is_blank(text));
+    if let Some(merged) = merged
+    {
+        println!("all replies: {}", format_replies(merged.iter().map(|reply| &reply[..])));
+    } else if config.mappings.is_empty()
+    {
+        println!("all replies: {}", format_replies(display_replies));
+    } else
+    {
+        let sorted_replies = sort_replies(
+            display_replies.collect::<Vec<&str>>(),
+            &column_mappings(config, label));
+
+        println!("sorted replies: {}", format_replies(sorted_replies.into_iter()));
+    }
+
+    Ok(())
+}
+
+fn shell_output(config: &Config, replies: &[&str]) -> String
+{
+    let no_label_replies = replies.iter().skip(1).copied();
+
+    let mut stats = vec![
+        ("question", replies[0].to_string()),
+        ("responses", no_label_replies.clone().filter(|text| !is_blank(text)).count().to_string()),
+        ("most popular", mode(no_label_replies.clone(), None).unwrap_or("").to_string())
+        ];
+
+    if !config.mappings.is_empty()
+    {
+        let mapped = map_replies(no_label_replies, &column_mappings(config, replies[0]));
+
+        stats.push(("average", format!("{:.2}", average(&mapped))));
+        stats.push(("median", format!("{:.2}", median(&mapped))));
+    }
+
+    stats.into_iter().map(|(name, value)| shell_variable(name, &value) + "\n").collect()
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn shell_variable(name: &str, value: &str) -> String
+{
+    let name: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+
+    let safe = !value.is_empty() && value.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "._-+:/".contains(c));
+
+    if safe
+    {
+        format!("QUIZ_{name}={value}")
+    } else
+    {
+        format!("QUIZ_{name}='{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+fn print_groups(config: &Config, answers: &Answers, group: &str) -> Result<(), Error>
+{
+    let index = question_index(config, answers).map_err(Error::Analysis)?;
+    let group_index = answers.index_of(group).ok_or_else(|| Error::Analysis(format!("cant find {group}")))?;
+
+    let label = &answers.labels()[index];
+    println!("{}", column_heading(config, index, label));
+    for line in group_lines(config, label, answers.group_by(group_index, index))
+    {
+        println!("{line}");
+    }
+
+    Ok(())
+}
+
+fn group_lines(config: &Config, label: &str, groups: Vec<(&str, Vec<&str>)>) -> Vec<String>
+{
+    let mappings = column_mappings(config, label);
+
+    groups.into_iter().map(|(group, replies)|
+    {
+        let mode = mode(replies.iter().copied(), None).unwrap_or("none");
+
+        let mut line = format!("{group}: most popular: {mode}");
+        if !config.mappings.is_empty()
+        {
+            let mapped = map_replies(replies.into_iter(), &mappings);
+            line.push_str(&format!(", average: {:.2}", average(&mapped)));
+        }
+
+        line
+    }).collect()
+}
+
+// rows are the answers to the selected question, columns the answers to the other one
+#[allow(clippy::suspicious_else_formatting)]
+fn print_crosstab(config: &Config, answers: &Answers, column: &str) -> Result<(), Error>
+{
+    let index = question_index(config, answers).map_err(Error::Analysis)?;
+    let column_index = answers.index_of(column).ok_or_else(|| Error::Analysis(format!("cant find {column}")))?;
+
+    let crosstab = CrossTab::new(answers, index, column_index)
+        .with_label(&column_heading(config, index, &answers.labels()[index]));
+
+    if let Some(path) = &config.crosstab_out
+    {
+        fs::write(path, crosstab.to_csv())?;
+    } else
+    {
+        crosstab.lines().iter().for_each(|line| println!("{line}"));
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn print_stream(config: &Config) -> Result<(), Error>
+{
+    let reader = open_input(&config.filepath)?;
+
+    let matches = |label: &str|
+    {
+        if config.exact
+        {
+            label==config.search
+        } else
+        {
+            label.contains(&config.search[..])
+        }
+    };
+
+    let mut counter = config.max_categories.map(SpaceSaving::new);
+    let mut occurrences: HashMap<String, u64> = HashMap::new();
+    let mut error = None;
+
+    let (index, label) = stream_choices(reader, &config.parse_options, matches, |choice|
+    {
+        let (choice, weight) = if config.weighted_choices
+        {
+            match split_weight(&choice)
+            {
+                Ok(split) => split,
+                Err(err) =>
+                {
+                    error.get_or_insert(err);
+                    return;
+                }
+            }
+        } else
+        {
+            (&choice[..], 1)
+        };
+
+        if let Some(counter) = counter.as_mut()
+        {
+            counter.insert_weighted(choice, weight as u64);
+        } else
+        {
+            let count = occurrences.entry(choice.to_owned()).or_insert(0);
+            *count = count.saturating_add(weight as u64);
+        }
+    }).map_err(Error::Parse)?.ok_or_else(|| Error::Analysis(format!("cant find {}", config.search)))?;
+
+    if let Some(error) = error
+    {
+        return Err(Error::Analysis(error));
+    }
+
+    let occurrences = counter.map(SpaceSaving::into_counts).unwrap_or(occurrences);
+
+    println!("{}", column_heading(config, index, &label));
+    for (reply, count) in sort_counts(with_scale(occurrences, &config.scale))
+    {
+        println!("{reply}: {count}");
+    }
+
+    Ok(())
+}
+
+fn print_avg_selections(config: &Config, answers: &Answers) -> Result<(), Error>
+{
+    let index = question_index(config, answers).map_err(Error::Analysis)?;
+
+    let average = average_count(&selection_counts(answers.cells(index)));
+
+    println!("{}", column_heading(config, index, &answers.labels()[index]));
+    println!("average selections: {average:.2}");
+
+    Ok(())
+}
+
+fn print_chi_square(config: &Config, replies: Vec<&str>) -> Result<(), Error>
+{
+    let label = replies[0];
+    let (statistic, freedom) = chi_square(replies.into_iter().skip(1), &config.expected)
+        .ok_or_else(|| Error::Analysis(String::from("no answers matching the expected ones")))?;
+
+    println!("{label}");
+    println!("chi-square: {statistic:.2}, degrees of freedom: {freedom}");
+
+    Ok(())
+}
+
+fn print_nps(config: &Config, replies: Vec<&str>) -> Result<(), Error>
+{
+    let label = replies[0];
+    let scores = scores(config, replies);
+
+    let [detractors, passives, promoters] = nps_counts(&scores);
+    let score = nps([detractors, passives, promoters]).ok_or_else(|| Error::Analysis(String::from("no scores between 0 and 10")))?;
+
+    println!("{label}");
+    println!("detractors: {detractors}, passives: {passives}, promoters: {promoters}");
+    println!("nps: {score:.1}");
+
+    Ok(())
+}
+
+// mapped values when there are mappings, otherwise the answers are the scores
+#[allow(clippy::suspicious_else_formatting)]
+fn scores(config: &Config, replies: Vec<&str>) -> Vec<f64>
+{
+    let label = replies[0];
+
+    if config.mappings.is_empty()
+    {
+        replies.into_iter().skip(1)
+            .filter_map(|reply| parse_number(reply, config.loose_numbers))
+            .collect()
+    } else
+    {
+        map_replies(replies.into_iter().skip(1), &column_mappings(config, label)).into_iter()
+            .map(f64::from)
+            .collect()
+    }
+}
+
+fn print_boxplot(config: &Config, replies: Vec<&str>) -> Result<(), Error>
+{
+    const WIDTH: usize = 40;
+
+    let label = replies[0];
+    let summary = five_number(&scores(config, replies)).ok_or_else(|| Error::Analysis(String::from("no numeric answers")))?;
+
+    let [min, q1, median, q3, max] = summary;
+
+    println!("{label}");
+    println!("min: {min:.2}, q1: {q1:.2}, median: {median:.2}, q3: {q3:.2}, max: {max:.2}");
+    println!("{}", boxplot(summary, WIDTH));
+
+    Ok(())
+}
+
+// min, lower quartile, median, upper quartile and max
+fn five_number(values: &[f64]) -> Option<[f64; 5]>
+{
+    if values.is_empty()
+    {
+        return None;
+    }
+
+    Some([0.0, 25.0, 50.0, 75.0, 100.0].map(|percent| float_percentile(values, percent)))
+}
+
+// whiskers out to min and max, the box between the quartiles with the median inside
+#[allow(clippy::suspicious_else_formatting)]
+fn boxplot(summary: [f64; 5], width: usize) -> String
+{
+    let [min, q1, median, q3, max] = summary;
+
+    let range = max-min;
+    let position = |value: f64|
+    {
+        if range==0.0
+        {
+            0
+        } else
+        {
+            ((value-min)/range*(width-1) as f64).round() as usize
+        }
+    };
+
+    let (low, high) = (position(q1), position(q3));
+
+    let mut line = vec![' '; position(max)+1];
+    line.iter_mut().take(low).for_each(|c| *c = '-');
+    line.iter_mut().skip(high).for_each(|c| *c = '-');
+
+    line[0] = '|';
+    line[position(max)] = '|';
+    line[low] = '[';
+    line[high] = ']';
+    line[position(median)] = '|';
+
+    line.into_iter().collect()
+}
+
+// detractors (0-6), passives (7-8) and promoters (9-10), anything else is left out
+fn nps_counts(scores: &[f64]) -> [usize; 3]
+{
+    let mut counts = [0; 3];
+    for &score in scores
+    {
+        if (0.0..7.0).contains(&score)
+        {
+            counts[0] += 1;
+        } else if (7.0..9.0).contains(&score)
+        {
+            counts[1] += 1;
+        } else if (9.0..=10.0).contains(&score)
+        {
+            counts[2] += 1;
+        }
+    }
+
+    counts
+}
+
+fn nps(counts: [usize; 3]) -> Option<f64>
+{
+    let total: usize = counts.iter().sum();
+    if total==0
+    {
+        return None;
+    }
+
+    let percent = |count: usize| count as f64/total as f64*100.0;
+    Some(percent(counts[2])-percent(counts[0]))
+}
+
+// goodness of fit against expected proportions, answers without one are left out
+fn chi_square<'a>(
+    replies: impl Iterator<Item=&'a str>,
+    expected: &[(String, f64)]
+) -> Option<(f64, usize)>
+{
+    let occurrences = frequencies(replies);
+
+    let observed: Vec<f64> = expected.iter()
+        .map(|(answer, _)| f64::from(occurrences.get(&answer[..]).copied().unwrap_or(0)))
+        .collect();
+
+    let total: f64 = observed.iter().sum();
+    if total==0.0
+    {
+        return None;
+    }
+
+    let proportions: f64 = expected.iter().map(|(_, proportion)| proportion).sum();
+
+    let statistic = observed.iter().zip(expected).map(|(observed, (_, proportion))|
+    {
+        let expected = total*proportion/proportions;
+        (observed-expected).powi(2)/expected
+    }).sum();
+
+    Some((statistic, expected.len()-1))
+}
+
+fn print_cumulative(config: &Config, replies: Vec<&str>) -> Result<(), Error>
+{
+    let label = replies[0];
+
+    println!("{label}");
+    for (point, percentage) in cumulative(replies.into_iter().skip(1), &config.scale)
+    {
+        println!("{point}: {percentage:.1}% at or above");
+    }
+
+    Ok(())
+}
+
+// answers outside of the scale are left out
+fn cumulative<'a, 'b>(
+    replies: impl Iterator<Item=&'a str>,
+    scale: &'b [String]
+) -> Vec<(&'b str, f64)>
+{
+    let occurrences = frequencies(replies);
+
+    let counts: Vec<u32> = scale.iter()
+        .map(|point| occurrences.get(&point[..]).copied().unwrap_or(0))
+        .collect();
+
+    let total = counts.iter().sum::<u32>().max(1);
+
+    let mut above = 0;
+    let mut out: Vec<(&str, f64)> = scale.iter().zip(counts).rev().map(|(point, count)|
+    {
+        above += count;
+        (&point[..], f64::from(above)*100.0/f64::from(total))
+    }).collect();
+
+    out.reverse();
+
+    out
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn print_validate_numeric(
+    config: &Config,
+    answers: &Answers,
+    label: &str,
+    heading: &str
+) -> Result<(), Error>
+{
+    let mut invalid = answers.validate_numeric(label).expect("label is from the answers");
+    if config.loose_numbers
+    {
+        invalid.retain(|value| parse_loose_number(value).is_none());
+    }
+
+    println!("{heading}");
+    if invalid.is_empty()
+    {
+        println!("all answers are numeric");
+    } else
+    {
+        println!("not numeric: {}", format_replies(invalid.into_iter()));
+    }
+
+    Ok(())
+}
+
+fn print_text_stats(answers: &Answers, label: &str, heading: &str) -> Result<(), Error>
+{
+    let lengths = answers.answer_lengths(label).expect("label is from the answers");
+
+    println!("{heading}");
+    match text_stats(&lengths)
+    {
+        Some((min, average, max)) =>
+        {
+            println!("length min: {min}, average: {average:.2}, max: {max}");
+        },
+        None => println!("no answers")
+    }
+
+    Ok(())
+}
+
+fn print_top_categories(
+    replies: Vec<&str>,
+    amount: usize,
+    counts: Option<Vec<u64>>
+) -> Result<(), Error>
+{
+    let label = replies[0];
+    let counts = counts.unwrap_or_else(|| vec![1; replies.len()-1]);
+
+    println!("{label}");
+    for (reply, count) in sort_counts(top_categories(replies.into_iter().skip(1).zip(counts), amount))
+    {
+        println!("{reply}: {count}");
+    }
+
+    Ok(())
+}
+
+// approximate counts of the most common answers, never tracks more than amount of them
+fn top_categories<'a>(
+    replies: impl Iterator<Item=(&'a str, u64)>,
+    amount: usize
+) -> HashMap<String, u64>
+{
+    let mut counter = SpaceSaving::new(amount);
+    replies.filter(|(text, _)| !is_blank(text))
+        .for_each(|(reply, count)| counter.insert_weighted(reply, count));
+
+    counter.into_counts()
+}
+
+// replaces every weighted choice with its text and returns the weights in the same order
+fn strip_weights(replies: &mut [&str]) -> Result<Vec<u64>, String>
+{
+    replies.iter_mut().skip(1).map(|reply|
+    {
+        let (text, weight) = split_weight(reply)?;
+        *reply = text;
+
+        Ok(weight as u64)
+    }).collect()
+}
+
+fn print_word_freq(config: &Config, replies: Vec<&str>, amount: usize) -> Result<(), Error>
+{
+    let label = replies[0];
+    let words = words(replies.into_iter().skip(1), config.skip_stopwords);
+
+    println!("{label}");
+    for (word, count) in distribution(words.iter().map(|word| &word[..])).into_iter().take(amount)
+    {
+        println!("{word}: {count}");
+    }
+
+    Ok(())
+}
+
+// lowercased with the punctuation around each word trimmed
+fn words<'a>(replies: impl Iterator<Item=&'a str>, skip_stopwords: bool) -> Vec<String>
+{
+    replies.flat_map(|reply| reply.split_whitespace())
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|word| !word.is_empty())
+        .filter(|word| !skip_stopwords || !STOPWORDS.contains(&&word[..]))
+        .collect()
+}
+
+fn text_stats(lengths: &[usize]) -> Option<(usize, f64, usize)>
+{
+    let min = *lengths.iter().min()?;
+    let max = *lengths.iter().max()?;
+
+    Some((min, average_count(lengths), max))
+}
+
+fn average_count(counts: &[usize]) -> f64
+{
+    if counts.is_empty()
+    {
+        return 0.0;
+    }
+
+    counts.iter().sum::<usize>() as f64/counts.len() as f64
+}
+
+fn selection_counts<'a>(cells: impl Iterator<Item=&'a [String]>) -> Vec<usize>
+{
+    cells.map(|cell| cell.iter().filter(|choice| !is_blank(choice)).count()).collect()
+}
+
+fn print_score_histogram(config: &Config, replies: Vec<&str>) -> Result<(), Error>
+{
+    if config.mappings.is_empty()
+    {
+        return Err(Error::Analysis(String::from("score histogram needs mappings")));
+    }
+
+    let label = replies[0];
+    println!("{label}");
+
+    let mapped = map_replies(replies.into_iter().skip(1), &column_mappings(config, label));
+
+    let bins: Vec<(String, u32)> = score_bins(&mapped).into_iter()
+        .map(|(score, count)| (score.to_string(), count))
+        .collect();
+
+    for line in histogram(&bins)
+    {
+        println!("{line}");
+    }
+
+    Ok(())
+}
+
+fn print_count_histogram(config: &Config, answers: &Answers) -> Result<(), Error>
+{
+    let index = question_index(config, answers).map_err(Error::Analysis)?;
+
+    println!("{}", column_heading(config, index, &answers.labels()[index]));
+    for line in histogram(&count_bins(&selection_counts(answers.cells(index))))
+    {
+        println!("{line}");
+    }
+
+    Ok(())
+}
+
+// every amount from zero up to the highest, so gaps show up as empty bars
+fn count_bins(counts: &[usize]) -> Vec<(String, u32)>
+{
+    let highest = counts.iter().max().copied().unwrap_or(0);
+
+    (0..=highest).map(|amount|
+    {
+        let respondents = counts.iter().filter(|count| **count==amount).count() as u32;
+        (amount.to_string(), respondents)
+    }).collect()
+}
+
+fn score_bins(mapped: &[i32]) -> Vec<(i32, u32)>
+{
+    let mut bins: BTreeMap<i32, u32> = BTreeMap::new();
+    for score in mapped
+    {
+        *bins.entry(*score).or_insert(0) += 1;
+    }
+
+    bins.into_iter().collect()
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn histogram(bins: &[(String, u32)]) -> Vec<String>
+{
+    const WIDTH: u32 = 40;
+
+    let label_width = bins.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    let highest = bins.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+
+    bins.iter().map(|(label, count)|
+    {
+        let length = if highest<=WIDTH
+        {
+            *count
+        } else
+        {
+            (u64::from(*count)*u64::from(WIDTH)/u64::from(highest)) as u32
+        };
+
+        let bar = "#".repeat(length as usize);
+        format!("{label:>label_width$} | {bar} {count}")
+    }).collect()
+}
+
+#[derive(PartialEq, Debug)]
+struct Comparison
+{
+    means: (f64, f64),
+    difference: f64,
+    t: Option<f64>
+}
+
+fn print_comparison(
+    config: &Config,
+    answers: &Answers,
+    column: &str,
+    names: [&str; 2]) -> Result<(), Error>
+{
+    if config.mappings.is_empty()
+    {
+        return Err(Error::Analysis(String::from("comparing groups needs mappings")));
+    }
+
+    let index = question_index(config, answers).map_err(Error::Analysis)?;
+    let group_index = answers.index_of(column).ok_or_else(|| Error::Analysis(format!("cant find {column}")))?;
+
+    let label = &answers.labels()[index];
+    let mappings = column_mappings(config, label);
+
+    let groups = answers.group_by(group_index, index);
+    let [first, second] = names.map(|name|
+    {
+        groups.iter().find(|(group, _)| *group==name)
+            .map(|(_, replies)| map_replies(replies.iter().copied(), &mappings))
+            .ok_or(format!("no respondents in group {name}"))
+    });
+
+    let (first, second) = (first.map_err(Error::Analysis)?, second.map_err(Error::Analysis)?);
+    let comparison = compare_means(&first, &second);
+
+    println!("{}", column_heading(config, index, label));
+    println!("{}: mean {:.2} (n={})", names[0], comparison.means.0, first.len());
+    println!("{}: mean {:.2} (n={})", names[1], comparison.means.1, second.len());
+
+    match comparison.t
+    {
+        Some(t) => println!("difference: {:.2}, t: {t:.2}", comparison.difference),
+        None => println!("difference: {:.2}", comparison.difference)
+    }
+
+    Ok(())
+}
+
+// welch's t statistic, only defined with at least two values on each side
+#[allow(clippy::suspicious_else_formatting)]
+fn compare_means(first: &[i32], second: &[i32]) -> Comparison
+{
+    let means = (average(first), average(second));
+    let difference = means.0-means.1;
+
+    let t = if first.len()<2 || second.len()<2
+    {
+        None
+    } else
+    {
+        let error = variance(first)/first.len() as f64
+            + variance(second)/second.len() as f64;
+
+        Some(difference/error.sqrt()).filter(|t| t.is_finite())
+    };
+
+    Comparison{means, difference, t}
+}
+
+fn print_breakdown(value: &str, replies: Vec<&str>) -> Result<(), Error>
+{
+    println!("{}", replies[0]);
+
+    let (matched, other) = breakdown(replies.into_iter().skip(1), value);
+    println!("{}", format_breakdown(matched, other));
+
+    Ok(())
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn breakdown<'a>(replies: impl Iterator<Item=&'a str>, value: &str) -> (u32, u32)
+{
+    replies.filter(|text| !is_blank(text))
+        .fold((0, 0), |(matched, other), reply|
+        {
+            if reply==value
+            {
+                (matched+1, other)
+            } else
+            {
+                (matched, other+1)
+            }
+        })
+}
+
+fn format_breakdown(matched: u32, other: u32) -> String
+{
+    let total = f64::from(matched+other).max(1.0);
+
+    let percent = |amount: u32| f64::from(amount)/total*100.0;
+    format!("matched {matched} ({:.0}%), other {other} ({:.0}%)", percent(matched), percent(other))
+}
+
+// one json object per respondent, every answer to a question where anyone picked more
+// than one choice is an array (empty if blank) so a key always has the same type
+#[allow(clippy::suspicious_else_formatting)]
+fn respondent_lines(answers: &Answers, uid_index: usize) -> Vec<String>
+{
+    let multi: Vec<bool> = (0..answers.labels().len())
+        .map(|index| answers.cells(index).any(|choices| choices.len()>1))
+        .collect();
+
+    answers.replies().iter().map(|reply|
+    {
+        let uid = reply[uid_index].join(";");
+
+        let cells = answers.labels().iter().zip(reply.iter()).zip(&multi);
+        let fields = cells.map(|((label, choices), multi)|
+        {
+            let value = if *multi
+            {
+                let choices = choices.iter().filter(|choice| !is_blank(choice));
+                json::array(choices.map(|choice| json::string(choice)))
+            } else
+            {
+                json::string(&choices.join(""))
+            };
+
+            (&label[..], value)
+        });
+
+        json::object([("uid", json::string(uid.trim())), ("answers", json::object(fields))]
+            .into_iter())
+    }).collect()
+}
+
+// every answer of a respondent mapped with the mappings of its own column
+fn respondent_mapped(config: &Config, labels: &[String], reply: &[Vec<String>]) -> Vec<i32>
+{
+    labels.iter().zip(reply).flat_map(|(label, choices)|
+    {
+        map_replies(choices.iter().map(|choice| &choice[..]), &column_mappings(config, label))
+    }).collect()
 }
 
 fn print_unique(
     config: &Config,
     answers: &Answers,
-    replies: Vec<&str>) -> Result<(), Box<dyn Error>>
+    replies: Vec<&str>) -> Result<(), Error>
 {
     for (index, uid) in replies.iter().skip(1).enumerate()
     {
-        let ureplies = answers.reply(index).ok_or("uid amount doesnt match to replies")?;
+        let reply = answers.reply(index).ok_or_else(|| Error::Analysis(String::from("uid amount doesnt match to replies")))?;
 
-        if ureplies.is_empty()
+        if reply.is_empty()
         {
             continue;
         }
 
-        let ureplies = ureplies.iter().flatten().map(|owned| &owned[..]);
+        let ureplies = reply.iter().flatten().map(|owned| &owned[..]);
 
         println!("{}:", uid.trim());
         println!("{{");
 
-        let mode = mode(ureplies.clone())
+        let mode = mode(ureplies.clone(), None)
             .expect("all users should have replies");
 
         println!("    most popular: {mode}");
 
         if !config.mappings.is_empty()
         {
-            let mapped = map_replies(ureplies, &config.mappings);
+            let mapped = respondent_mapped(config, answers.labels(), reply);
 
             let median = median(&mapped);
             let average = average(&mapped);
@@ -209,42 +3775,268 @@ fn print_unique(
     Ok(())
 }
 
-fn print_ranked(config: &Config, answers: Answers) -> Result<(), Box<dyn Error>>
+#[allow(clippy::suspicious_else_formatting)]
+fn print_ranked(config: &Config, answers: Answers) -> Result<(), Error>
 {
-    let labels = answers.labels();
-    let replies = answers.replies();
+    let label_sums = if config.bayesian_rank
+    {
+        bayesian_ranked(config, &answers)
+    } else
+    {
+        ranked(config, &answers)
+    };
+
+    ranked_lines(config, answers.labels(), label_sums).iter().for_each(|line| println!("{line}"));
+
+    Ok(())
+}
+
+fn ranked_lines(config: &Config, labels: &[String], label_sums: Vec<(&str, Option<f64>)>) -> Vec<String>
+{
+    filter_ranked(config, label_sums).into_iter().map(|(label, average)|
+    {
+        format!("{}: {}", heading(config, labels, label), ranked_average(average))
+    }).collect()
+}
 
-    let mut sums = vec![0; labels.len()];
-    for (index, _) in labels.iter().enumerate()
+// columns nobody answered have no average instead of a misleading zero
+fn ranked_average(average: Option<f64>) -> String
+{
+    match average
     {
-        for reply in replies
+        Some(average) => format!("average {average:.2}"),
+        None => String::from("no responses")
+    }
+}
+
+fn filter_ranked<'a>(
+    config: &Config,
+    label_sums: Vec<(&'a str, Option<f64>)>
+) -> Vec<(&'a str, Option<f64>)>
+{
+    let kept = config.top_percent.map_or(label_sums.len(), |percent| top_count(label_sums.len(), percent));
+
+    label_sums.into_iter()
+        .take(kept)
+        .filter(|(_, average)|
         {
-            let mapped = reply[index].iter()
-                .fold(0, |acc, current| acc+config.mappings.get(current).unwrap_or(&0));
-            sums[index] += mapped;
-        }
+            config.min_average.is_none_or(|min| average.is_some_and(|average| average>=min))
+        })
+        .collect()
+}
+
+// rounded up so theres always at least one question left
+fn top_count(amount: usize, percent: f64) -> usize
+{
+    ((amount as f64*percent/100.0).ceil() as usize).max(1).min(amount)
+}
+
+fn print_ranked_state(config: &Config, answers: &Answers, path: &str) -> Result<(), Error>
+{
+    let mut aggregate = Aggregate::from_answers(answers, |label| column_mappings(config, label));
+
+    if Path::new(path).exists()
+    {
+        let mut saved = Aggregate::deserialize(&fs::read_to_string(path)?).map_err(Error::Parse)?;
+        saved.merge(aggregate).map_err(Error::Analysis)?;
+
+        aggregate = saved;
     }
 
-    let scale = replies.len() as f64;
-    let mut label_sums: Vec<(&str, f64)> = sums.iter().enumerate()
-        .map(|(index, value)|
+    fs::write(path, aggregate.serialize())?;
+
+    ranked_lines(config, answers.labels(), aggregate.ranked()).iter().for_each(|line| println!("{line}"));
+
+    Ok(())
+}
+
+fn ranked<'a>(config: &Config, answers: &'a Answers) -> Vec<(&'a str, Option<f64>)>
+{
+    let labels = answers.labels();
+
+    let scale = answers.replies().len() as f64;
+    // unmapped answers still count as responses, only columns nobody answered have no average
+    let label_sums: Vec<(&str, Option<f64>)> = column_totals(config, answers).into_iter()
+        .enumerate()
+        .map(|(index, (sum, _))|
         {
-            (&labels[index][..], f64::from(value.clone())/scale)
+            (&labels[index][..], answered(answers, index).then(|| f64::from(sum)/scale))
         })
         .skip(1)
         .collect();
 
+    sort_ranked(label_sums)
+}
+
+// pulls questions with few responses towards the mean of all questions
+fn bayesian_ranked<'a>(config: &Config, answers: &'a Answers) -> Vec<(&'a str, Option<f64>)>
+{
+    let labels = answers.labels();
+    let totals: Vec<(i32, u32)> = column_totals(config, answers).into_iter().skip(1).collect();
+
+    let (sum, count) = totals.iter()
+        .fold((0, 0), |(sum, count), current| (sum+current.0, count+current.1));
+
+    let mean = f64::from(sum)/f64::from(count.max(1));
+    let prior = config.prior.unwrap_or(f64::from(count)/totals.len().max(1) as f64);
+
+    // the prior alone is no response, columns nobody answered stay without an average
+    let label_sums: Vec<(&str, Option<f64>)> = totals.into_iter().enumerate()
+        .map(|(index, (sum, count))|
+        {
+            let average = (prior*mean+f64::from(sum))/(prior+f64::from(count));
+            (&labels[index+1][..], answered(answers, index+1).then_some(average))
+        })
+        .collect();
+
+    sort_ranked(label_sums)
+}
+
+fn answered(answers: &Answers, index: usize) -> bool
+{
+    answers.cells(index).flatten().any(|choice| !is_blank(choice))
+}
+
+fn column_totals(config: &Config, answers: &Answers) -> Vec<(i32, u32)>
+{
+    answers.labels().iter().enumerate().map(|(index, label)|
+    {
+        let mappings = column_mappings(config, label);
+
+        answers.cells(index).flatten()
+            .filter_map(|choice| mappings.get(choice))
+            .fold((0, 0), |(sum, count), value| (sum+value, count+1))
+    }).collect()
+}
+
+fn sort_ranked(mut label_sums: Vec<(&str, Option<f64>)>) -> Vec<(&str, Option<f64>)>
+{
     label_sums.sort_by(|other, current|
     {
         current.1.partial_cmp(&other.1).unwrap_or(Ordering::Less)
     });
 
-    for sum in label_sums
+    label_sums
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn report(config: &Config, answers: &Answers) -> String
+{
+    let mut out = String::from("# Report\n");
+
+    out.push_str("\n## Summary\n\n");
+    out.push_str("| question | responses | most popular | average |\n");
+    out.push_str("|---|---|---|---|\n");
+
+    let questions: Vec<Vec<&str>> = answers.iter_questions()
+        .filter(|replies| in_summary(config, replies[0]))
+        .collect();
+
+    for replies in &questions
     {
-        println!("{}: average {:.2}", sum.0, sum.1);
+        let no_label_replies = replies.iter().skip(1).copied();
+
+        let responses = no_label_replies.clone().filter(|text| !is_blank(text)).count();
+        let mode = mode(no_label_replies.clone(), None).unwrap_or("");
+
+        let average = if config.mappings.is_empty()
+        {
+            String::new()
+        } else
+        {
+            let mappings = column_mappings(config, replies[0]);
+            format!("{:.2}", average(&map_replies(no_label_replies, &mappings)))
+        };
+
+        out.push_str(&format!("| {} | {responses} | {} | {average} |\n",
+            markdown_cell(&heading(config, answers.labels(), replies[0])), markdown_cell(mode)));
     }
 
-    Ok(())
+    out.push_str("\n## Distributions\n");
+    for replies in &questions
+    {
+        out.push_str(&format!("\n### {}\n\n", heading(config, answers.labels(), replies[0])));
+
+        let occurrences = frequencies(replies.iter().skip(1).copied());
+        for (reply, count) in sort_counts(with_scale(occurrences, &config.scale))
+        {
+            out.push_str(&format!("- {reply}: {count}\n"));
+        }
+    }
+
+    out.push_str("\n## Ranking\n\n");
+    if config.mappings.is_empty()
+    {
+        out.push_str("no mappings given\n");
+    } else
+    {
+        let label_sums = ranked(config, answers).into_iter()
+            .filter(|(label, _)| in_summary(config, label));
+
+        for (index, (label, average)) in label_sums.enumerate()
+        {
+            out.push_str(&format!("{}. {label}: {}\n", index+1, ranked_average(average)));
+        }
+    }
+
+    out
+}
+
+// --columns-regex limits which questions the summaries include
+fn in_summary(config: &Config, label: &str) -> bool
+{
+    config.columns_regex.as_ref().is_none_or(|regex| regex.is_match(label))
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn oneline(config: &Config, answers: &Answers) -> Vec<String>
+{
+    answers.iter_questions().filter(|replies| in_summary(config, replies[0])).map(|replies|
+    {
+        let label = heading(config, answers.labels(), replies[0]).replace(['\t', '\n'], " ");
+        let no_label_replies = replies.iter().skip(1).copied();
+
+        let responses = no_label_replies.clone().filter(|text| !is_blank(text)).count();
+        let mode = mode(no_label_replies.clone(), None).unwrap_or("").replace(['\t', '\n'], " ");
+
+        let average = if config.mappings.is_empty()
+        {
+            String::new()
+        } else
+        {
+            let mappings = column_mappings(config, replies[0]);
+            format!("{:.2}", average(&map_replies(no_label_replies, &mappings)))
+        };
+
+        format!("{label}\t{responses}\t{mode}\t{average}")
+    }).collect()
+}
+
+// --index-prefix puts the column index in front so output lines up across survey versions
+fn heading<'a>(config: &Config, labels: &[String], label: &'a str) -> Cow<'a, str>
+{
+    match labels.iter().position(|current| current==label)
+    {
+        Some(index) => column_heading(config, index, label),
+        None => Cow::Borrowed(label)
+    }
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn column_heading<'a>(config: &Config, index: usize, label: &'a str) -> Cow<'a, str>
+{
+    if config.index_prefix
+    {
+        Cow::Owned(format!("[{index}] {label}"))
+    } else
+    {
+        Cow::Borrowed(label)
+    }
+}
+
+fn markdown_cell(text: &str) -> String
+{
+    text.replace('|', "\\|").replace('\n', " ")
 }
 
 fn format_replies<'a>(replies: impl Iterator<Item=&'a str>) -> String
@@ -261,60 +4053,330 @@ fn format_replies<'a>(replies: impl Iterator<Item=&'a str>) -> String
     out
 }
 
-fn mode<'a>(replies: impl Iterator<Item=&'a str>) -> Option<&'a str>
+fn merged_replies<'a>(cells: impl Iterator<Item=&'a [String]>, separator: &str) -> Vec<String>
+{
+    cells.map(|cell|
+    {
+        cell.iter().filter(|choice| !is_blank(choice))
+            .map(|choice| &choice[..])
+            .collect::<Vec<&str>>()
+            .join(separator)
+    }).filter(|reply| !reply.is_empty()).collect()
+}
+
+// answers with only whitespace count as blank
+fn is_blank(text: &str) -> bool
+{
+    text.trim().is_empty()
+}
+
+fn frequencies<'a>(replies: impl Iterator<Item=&'a str>) -> HashMap<&'a str, u32>
 {
     let mut occurrences: HashMap<&str, u32> = HashMap::new();
-    for reply in replies.filter(|text| !text.is_empty())
+    for reply in replies.filter(|text| !is_blank(text))
     {
         let current = occurrences.entry(reply).or_insert(0);
         *current += 1;
     }
 
-    if occurrences.is_empty()
+    occurrences
+}
+
+fn distribution<'a>(replies: impl Iterator<Item=&'a str>) -> Vec<(&'a str, u32)>
+{
+    sort_counts(frequencies(replies))
+}
+
+// every point of the declared scale is included, even if nobody picked it
+fn with_scale<'a, K, C>(mut occurrences: HashMap<K, C>, scale: &'a [String]) -> HashMap<K, C>
+    where
+        K: Eq + Hash + From<&'a str>,
+        C: Default
+{
+    for point in scale
+    {
+        occurrences.entry(K::from(point)).or_default();
+    }
+
+    occurrences
+}
+
+fn sort_counts<T: Ord, C: Ord>(counts: impl IntoIterator<Item=(T, C)>) -> Vec<(T, C)>
+{
+    let mut counts: Vec<(T, C)> = counts.into_iter().collect();
+    counts.sort_by(|other, current| current.1.cmp(&other.1).then(other.0.cmp(&current.0)));
+
+    counts
+}
+
+// weights line up with the replies, no weights or replies past their end count once
+#[allow(clippy::suspicious_else_formatting)]
+fn mode<'a>(replies: impl Iterator<Item=&'a str>, weights: Option<&[f64]>) -> Option<&'a str>
+{
+    let weights = weights.into_iter().flatten().copied().chain(iter::repeat(1.0));
+
+    let mut totals: HashMap<&str, f64> = HashMap::new();
+    for (reply, weight) in replies.zip(weights).filter(|(text, _)| !is_blank(text))
+    {
+        *totals.entry(reply).or_insert(0.0) += weight;
+    }
+
+    if totals.is_empty()
     {
         return None;
     }
 
-    let most = occurrences.iter().fold(
-        occurrences.iter().next().expect("map should not be empty"),
+    let most = totals.iter().fold(
+        totals.iter().next().expect("map should not be empty"),
         |highest, current|
         {
             if current.1>highest.1
             {
-                return current;
+                current
             } else
             {
-                return highest;
+                highest
             }
         });
 
     Some(most.0)
 }
 
+// the weight of the respondent for every choice of the question, in the same order as
+// the collected replies, respondents without a numeric weight count once
+fn choice_weights(answers: &Answers, index: usize, weight_index: usize) -> Result<Vec<f64>, Error>
+{
+    let mut weights = Vec::new();
+    for (row, reply) in answers.replies().iter().enumerate()
+    {
+        let text = reply[weight_index].first().map(|text| text.trim());
+        let weight = text.and_then(|text| text.parse::<f64>().ok()).unwrap_or(1.0);
+
+        if !weight.is_finite() || weight<0.0
+        {
+            return Err(Error::Parse(format!("row {} has an invalid weight {}",
+                row+1, text.unwrap_or_default())));
+        }
+
+        weights.extend(reply[index].iter().map(|_| weight));
+    }
+
+    Ok(weights)
+}
+
 fn map_replies<'a>(
     replies: impl Iterator<Item=&'a str>,
     mapping: &HashMap<String, i32>) -> Vec<i32>
 {
-    replies.filter(|choice| mapping.get(choice.clone()).is_some())
+    replies.filter(|choice| mapping.contains_key(*choice))
         .map(|choice|
         {
-            mapping.get(choice)
-            .expect("all invalid values should be filtered").clone()
+            *mapping.get(choice)
+            .expect("all invalid values should be filtered")
         }).collect()
 }
 
+#[allow(clippy::suspicious_else_formatting)]
+fn column_mappings<'a>(config: &'a Config, label: &str) -> Cow<'a, HashMap<String, i32>>
+{
+    if config.reversed.iter().any(|reversed| label.contains(&reversed[..]))
+    {
+        Cow::Owned(reverse_mappings(&config.mappings))
+    } else
+    {
+        Cow::Borrowed(&config.mappings)
+    }
+}
+
+fn reverse_mappings(mapping: &HashMap<String, i32>) -> HashMap<String, i32>
+{
+    let lowest = mapping.values().min().copied().unwrap_or(0);
+    let highest = mapping.values().max().copied().unwrap_or(0);
+
+    mapping.iter().map(|(choice, value)| (choice.clone(), lowest+highest-value)).collect()
+}
+
+fn scale_mappings(scale: &[String]) -> HashMap<String, i32>
+{
+    scale.iter().zip(1..).map(|(point, value)| (point.clone(), value)).collect()
+}
+
+// the answers mapped closest to the value, lower values win ties
+fn median_label(mappings: &HashMap<String, i32>, value: f64) -> Option<String>
+{
+    let distance = |mapped: i32| (f64::from(mapped)-value).abs();
+
+    let closest = mappings.values().copied().min_by(|other, current|
+    {
+        distance(*other).total_cmp(&distance(*current)).then(other.cmp(current))
+    })?;
+
+    let mut answers: Vec<&str> = mappings.iter()
+        .filter(|(_, mapped)| **mapped==closest)
+        .map(|(answer, _)| &answer[..])
+        .collect();
+
+    answers.sort_unstable();
+
+    Some(answers.join("/"))
+}
+
+fn mapping_lines(mappings: &HashMap<String, i32>) -> Vec<String>
+{
+    let mut entries: Vec<(&String, &i32)> = mappings.iter().collect();
+    entries.sort_by(|other, current| (other.1, other.0).cmp(&(current.1, current.0)));
+
+    entries.into_iter().map(|(answer, value)| format!("{answer} = {value}")).collect()
+}
+
 fn sort_replies<'a>(mut replies: Vec<&'a str>, mapping: &HashMap<String, i32>) -> Vec<&'a str>
 {
     replies.sort_by(|other, current|
     {
-        let other = mapping.get(other.clone());
-        let current = mapping.get(current.clone());
+        let other = mapping.get(*other);
+        let current = mapping.get(*current);
         other.cmp(&current)
     });
 
     replies
 }
 
+fn stat(name: &str, slice: &[i32]) -> f64
+{
+    match name
+    {
+        "average" => average(slice),
+        "median" => median(slice),
+        "iqr" => iqr(slice),
+        "std_dev" => std_dev(slice),
+        "cv" => cv(slice),
+        _ => unreachable!("stat names are checked when building the config")
+    }
+}
+
+fn rng(config: &Config) -> Rng
+{
+    let seed = config.seed.unwrap_or_else(||
+    {
+        SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0)
+    });
+
+    Rng::new(seed)
+}
+
+// a random sample of up to per_group respondents from every group, blank groups are dropped
+fn balanced_indices(answers: &Answers, index: usize, per_group: usize, rng: &mut Rng) -> Vec<usize>
+{
+    let mut groups: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (reply_index, cell) in answers.cells(index).enumerate()
+    {
+        if let Some(group) = cell.iter().find(|choice| !is_blank(choice))
+        {
+            groups.entry(group).or_default().push(reply_index);
+        }
+    }
+
+    groups.into_values().flat_map(|mut members|
+    {
+        let amount = per_group.min(members.len());
+        for current in 0..amount
+        {
+            let picked = current+rng.below(members.len()-current);
+            members.swap(current, picked);
+        }
+
+        members.truncate(amount);
+        members
+    }).collect()
+}
+
+// 2.5th and 97.5th percentiles of the means of resampled values
+fn bootstrap(values: &[i32], iterations: usize, rng: &mut Rng) -> Option<(f64, f64)>
+{
+    if values.is_empty() || iterations==0
+    {
+        return None;
+    }
+
+    let means: Vec<f64> = (0..iterations).map(|_|
+    {
+        let total: i64 = (0..values.len()).map(|_| i64::from(values[rng.below(values.len())])).sum();
+        total as f64/values.len() as f64
+    }).collect();
+
+    Some((float_percentile(&means, 2.5), float_percentile(&means, 97.5)))
+}
+
+fn percentile(slice: &[i32], percent: f64) -> f64
+{
+    let values: Vec<f64> = slice.iter().map(|value| f64::from(*value)).collect();
+
+    float_percentile(&values, percent)
+}
+
+fn float_percentile(slice: &[f64], percent: f64) -> f64
+{
+    if slice.is_empty()
+    {
+        return 0.0;
+    }
+
+    let mut sorted: Vec<f64> = Vec::from(slice);
+    sorted.sort_by(f64::total_cmp);
+
+    let rank = percent/100.0*(sorted.len()-1) as f64;
+
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+
+    let fraction = rank-lower as f64;
+    sorted[lower]+(sorted[upper]-sorted[lower])*fraction
+}
+
+fn iqr(slice: &[i32]) -> f64
+{
+    if slice.len()<2
+    {
+        return 0.0;
+    }
+
+    percentile(slice, 75.0)-percentile(slice, 25.0)
+}
+
+// sample variance
+fn variance(slice: &[i32]) -> f64
+{
+    if slice.len()<2
+    {
+        return 0.0;
+    }
+
+    let mean = average(slice);
+    let squares: f64 = slice.iter().map(|value| (f64::from(*value)-mean).powi(2)).sum();
+
+    squares/(slice.len()-1) as f64
+}
+
+fn std_dev(slice: &[i32]) -> f64
+{
+    variance(slice).sqrt()
+}
+
+// coefficient of variation, zero when the mean is zero
+fn cv(slice: &[i32]) -> f64
+{
+    let mean = average(slice);
+    if mean==0.0
+    {
+        return 0.0;
+    }
+
+    std_dev(slice)/mean.abs()
+}
+
+#[allow(clippy::suspicious_else_formatting)]
 fn median(slice: &[i32]) -> f64
 {
     if slice.is_empty()
@@ -324,11 +4386,11 @@ fn median(slice: &[i32]) -> f64
 
     let amount = slice.len();
 
-    let mut sorted: Vec<i32> = Vec::from(slice.clone());
+    let mut sorted: Vec<i32> = Vec::from(slice);
     sorted.sort();
 
     let middle = amount/2;
-    if amount%2==0
+    if amount.is_multiple_of(2)
     {
         let upper = sorted[middle];
         let lower = sorted[middle-1];
@@ -349,7 +4411,7 @@ fn average(slice: &[i32]) -> f64
 
     let amount: u32 = slice.len().try_into().expect("cant convert usize to u32");
 
-    let total = slice.iter().fold(0, |acc, current| acc+current);
+    let total = slice.iter().sum::<i32>();
 
     f64::from(total)/f64::from(amount)
 }
\ No newline at end of file
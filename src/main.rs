@@ -1,19 +1,92 @@
 use std::env;
 use std::process;
 
-use quizanalyze::Config;
+use quizanalyze::{Config, Error};
 
 fn help_message(program: &str) -> String
 {
     let mut message = String::from("usage: ");
     message.push_str(program);
     message.push_str(" -s \"search string\" [args] /path/to/file");
+    message.push_str("\n\nthe file can be gzipped, - reads from stdin");
     message.push_str("\n\nargs:");
     message.push_str("\n    -s    question to search");
     message.push_str("\n    -r, --rank    ranks all the questions by mapping");
+    message.push_str("\n    --bayesian-rank    rank by a bayesian average so questions with few answers dont dominate");
+    message.push_str("\n    --prior    weight of the overall mean for --bayesian-rank, average responses per question by default");
+    message.push_str("\n    --min-average    only rank questions averaging at least this much");
+    message.push_str("\n    --top-percent    only rank the best <p> percent of questions, at least one (<p>)");
     message.push_str("\n    -u, --unique    the question is an uid");
+    message.push_str("\n    --respondents-jsonl    print every respondent as a json line with their uid and answers");
+    message.push_str("\n    --list-columns    print the index of every question and exit");
+    message.push_str("\n    --col    question to select by index, negative counts from the end");
+    message.push_str("\n    --uid-col    index of the uid column for --unique");
+    message.push_str("\n    --uid-first    use the first column as the uid");
+    message.push_str("\n    --dedupe-uid    only keep the last response of every uid");
     message.push_str("\n    -e, --exact    only include exact matches");
+    message.push_str("\n    --collapse-whitespace    treat answers differing only in whitespace as the same");
+    message.push_str("\n    --dedupe-answers    count a choice picked twice in the same answer once");
+    message.push_str("\n    --sanitize    show control characters in the text as ^X");
     message.push_str("\n    -m    map choices to numbers (<split character>choice<split character>number)");
+    message.push_str("\n    --mapping-report    print the mapping in effect sorted by value before the analysis");
+    message.push_str("\n    --merge-sep    list every respondent's choices joined by a separator instead of one by one (<separator>)");
+    message.push_str("\n    --oneline    print every question as label, responses, most popular, average separated by tabs");
+    message.push_str("\n    --columns-regex    only include questions matching a pattern in --oneline, --report and the analysis report (<regex>)");
+    message.push_str("\n    --fail-on-empty    fail if the question has no responses");
+    message.push_str("\n    --stats    mapped stats to print, comma separated (average, median, iqr, std_dev, cv)");
+    message.push_str("\n    --bootstrap    print a 95% bootstrap interval of the mapped mean (<iterations>)");
+    message.push_str("\n    --seed    seed for random resampling, the current time by default (<number>)");
+    message.push_str("\n    --shell    print the stats as shell variable assignments");
+    message.push_str("\n    --group-by    split respondents by their answer to another question (<question>)");
+    message.push_str("\n    --avg-selections    average amount of choices picked per respondent");
+    message.push_str("\n    --validate-numeric    list the answers that arent numbers");
+    message.push_str("\n    --expected    expected share of every answer for --chi-square (<answer>=<proportion>,...)");
+    message.push_str("\n    --chi-square    compare the answers to the --expected ones");
+    message.push_str("\n    --nps    net promoter score of a 0-10 question, uses the mapped values if there are mappings");
+    message.push_str("\n    --boxplot    five number summary of a numeric or mapped question with an ascii boxplot");
+    message.push_str("\n    --explain-mapping-coverage    how much of every columns answers the mappings cover, worst first");
+    message.push_str("\n    --json-schema    print the json schema of the analysis report, needs no file");
+    message.push_str("\n    --json    print the analysis report of the selected question, or every question, as json");
+    message.push_str("\n    --scale    ordered answers of an ordinal question from lowest to highest, listed even if nobody picked them, optionally with their mapped values (<answer>,... or <answer>=<value>,...)");
+    message.push_str("\n    --cumulative    percentage of answers at or above every point of the --scale");
+    message.push_str("\n    --loose-numbers    read numbers like $1,234.50 or 1 234,50 in --duration-col and --validate-numeric");
+    message.push_str("\n    --parse-stats    print how many lines were read, skipped as blank and kept as rows");
+    message.push_str("\n    --sort-by    order respondents by the first answer of a column before printing, numbers numerically (<label>)");
+    message.push_str("\n    --text-stats    shortest, average and longest answer length in characters");
+    message.push_str("\n    --word-freq    most common words in the answers (<amount>)");
+    message.push_str("\n    --max-categories    approximate counts of the most common answers, tracking at most <n> of them, works with --stream (<n>)");
+    message.push_str("\n    --weighted-choices    count choices like option:3 as their trailing weight instead of once in the most popular answer, --max-categories and --stream");
+    message.push_str("\n    --collate    write the cleaned up answers as csv, also analyzes them if a question is given (<path>)");
+    message.push_str("\n    --skip-stopwords    leave common words like the and is out of --word-freq");
+    message.push_str("\n    --count-per-respondent    chart how many choices every respondent picked");
+    message.push_str("\n    --score-histogram    chart how many answers mapped to each number");
+    message.push_str("\n    --assert-average    fail unless a question averages within a range (<question>:<low>-<high>)");
+    message.push_str("\n    --stream    count the answers to a question without loading the whole file");
+    message.push_str("\n    --duration-col    time spent stats from a column of seconds (<question>)");
+    message.push_str("\n    --trend    mapped average of a question over time (<question>)");
+    message.push_str("\n    --by    date bucket for --trend, month by default (day, month, year)");
+    message.push_str("\n    --date-col    date column for --trend, the first column by default (<question>)");
+    message.push_str("\n    --min-duration    responses faster than this are flagged, half the median by default");
+    message.push_str("\n    --compare-groups    compare the mapped mean between two groups (<question>:<group>,<group>)");
+    message.push_str("\n    --jaccard    average overlap of every respondent's choices for two questions (<question>,<question>)");
+    message.push_str("\n    --crosstab    count every pair of answers to the question and another one (<question>)");
+    message.push_str("\n    --crosstab-out    write the --crosstab table to a csv file instead (<path>)");
+    message.push_str("\n    --breakdown    count answers matching a value against all others (<value>)");
+    message.push_str("\n    --require-column    fail if a column with this exact label is missing, repeatable");
+    message.push_str("\n    --fixed-width    read fixed width columns instead of csv (<width>,<width>,...)");
+    message.push_str("\n    --timeout    give up if parsing takes longer than this many seconds (<seconds>)");
+    message.push_str("\n    --no-blank-lines    fail on blank lines instead of skipping them");
+    message.push_str("\n    --require-rectangular    list every row with the wrong amount of fields instead of only failing");
+    message.push_str("\n    --state    merge the ranked totals with ones saved in a file and save them back (<path>)");
+    message.push_str("\n    --reverse    flip the mapped values of a reverse coded question, repeatable (<question>)");
+    message.push_str("\n    --cohort    only analyze respondents who gave the most popular answer to a question (<question>)");
+    message.push_str("\n    --weight    weigh every respondent by a numeric column when picking the most popular answer (<label>)");
+    message.push_str("\n    --balance    only keep a random sample of respondents from every group of a question (<question>)");
+    message.push_str("\n    --per-group    respondents kept per group for --balance (<amount>)");
+    message.push_str("\n    --range    only analyze respondents in a range (<start>:<end>)");
+    message.push_str("\n    --index-prefix    put the column index in front of question labels in the output");
+    message.push_str("\n    --stamp    end the output with the filepath and time of the analysis");
+    message.push_str("\n    --report    write a markdown report of every question to a file (<path>)");
 
     message
 }
@@ -24,7 +97,7 @@ fn main()
     {
         eprintln!("error parsing args: {err}");
 
-        eprintln!("{}", help_message(&env::args().nth(0)
+        eprintln!("{}", help_message(&env::args().next()
             .expect("first program argument should always exist")));
 
         process::exit(1);
@@ -33,6 +106,9 @@ fn main()
     if let Err(err) = quizanalyze::run(&config)
     {
         eprintln!("application error: {err}");
-        process::exit(2);
+
+        // bad options found only once the file is read are still the callers fault
+        let code = if matches!(err, Error::Config(_)) { 1 } else { 2 };
+        process::exit(code);
     };
 }
\ No newline at end of file
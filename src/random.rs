@@ -0,0 +1,30 @@
+// small splitmix64 generator, good enough for resampling and reproducible with a seed
+pub struct Rng
+{
+    state: u64
+}
+
+impl Rng
+{
+    pub fn new(seed: u64) -> Self
+    {
+        Rng{state: seed}
+    }
+
+    pub fn next_u64(&mut self) -> u64
+    {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+        z ^ (z >> 31)
+    }
+
+    // uniform index below the limit
+    pub fn below(&mut self, limit: usize) -> usize
+    {
+        ((u128::from(self.next_u64())*limit as u128) >> 64) as usize
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+// space saving top counter, keeps at most capacity answers around, when its full the
+// least counted one is replaced and the newcomer inherits its count so counts can only
+// be overestimated and every answer seen more than total/capacity times is kept
+pub struct SpaceSaving
+{
+    capacity: usize,
+    counts: HashMap<String, u64>
+}
+
+impl SpaceSaving
+{
+    pub fn new(capacity: usize) -> Self
+    {
+        SpaceSaving{capacity, counts: HashMap::with_capacity(capacity)}
+    }
+
+    // counts saturate instead of overflowing
+    pub fn insert_weighted(&mut self, answer: &str, weight: u64)
+    {
+        if weight==0
+        {
+            return;
+        }
+
+        if let Some(count) = self.counts.get_mut(answer)
+        {
+            *count = count.saturating_add(weight);
+            return;
+        }
+
+        if self.counts.len()<self.capacity
+        {
+            self.counts.insert(answer.to_owned(), weight);
+            return;
+        }
+
+        let lowest = self.counts.iter()
+            .min_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(answer, count)| (answer.clone(), *count));
+
+        if let Some((evicted, count)) = lowest
+        {
+            self.counts.remove(&evicted);
+            self.counts.insert(answer.to_owned(), count.saturating_add(weight));
+        }
+    }
+
+    pub fn into_counts(self) -> HashMap<String, u64>
+    {
+        self.counts
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn heavy_hitters()
+    {
+        let mut counter = SpaceSaving::new(3);
+
+        let mut total = 0;
+        for round in 0..50
+        {
+            counter.insert_weighted("cats", 1);
+            counter.insert_weighted(&format!("rare {round}"), 1);
+            total += 2;
+
+            if round%2==0
+            {
+                counter.insert_weighted("dogs", 1);
+                total += 1;
+            }
+        }
+
+        let counts = counter.into_counts();
+        assert!(counts.len()<=3);
+
+        // both are above total/capacity so they must survive, never undercounted
+        assert!(counts["cats"]>=50);
+        assert!(counts["dogs"]>=25);
+        assert!(counts["cats"]<=50+total/3);
+        assert!(counts["cats"]>counts["dogs"]);
+
+        let mut exact = SpaceSaving::new(5);
+        ["a", "b", "a", "c", "a", "b"].into_iter().for_each(|answer| exact.insert_weighted(answer, 1));
+        assert_eq!(exact.into_counts(), HashMap::from([
+            (String::from("a"), 3), (String::from("b"), 2), (String::from("c"), 1)
+            ]));
+
+        assert!(SpaceSaving::new(0).into_counts().is_empty());
+    }
+
+    #[test]
+    fn weighted()
+    {
+        let mut counter = SpaceSaving::new(2);
+        counter.insert_weighted("a", 4_000_000_000);
+        counter.insert_weighted("a", 4_000_000_000);
+        counter.insert_weighted("b", 0);
+        counter.insert_weighted("c", 1);
+
+        assert_eq!(counter.into_counts(), HashMap::from([
+            (String::from("a"), 8_000_000_000), (String::from("c"), 1)
+            ]));
+
+        let mut counter = SpaceSaving::new(1);
+        counter.insert_weighted("a", u64::MAX);
+        counter.insert_weighted("a", 5);
+        counter.insert_weighted("b", 5);
+
+        assert_eq!(counter.into_counts(), HashMap::from([(String::from("b"), u64::MAX)]));
+    }
+}